@@ -0,0 +1,133 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Client as Redis, Msg, ToRedisArgs};
+use serde::de::DeserializeOwned;
+
+use crate::client::Client;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::listener::Listener;
+
+/// The non-blocking counterpart to [`Client`], for tokio services. Commands
+/// go through a [`ConnectionManager`], which reconnects on its own after the
+/// connection drops, and the client is cheap to clone and share.
+#[derive(Clone)]
+pub struct AsyncClient {
+    pub config: Config,
+    pub connection: ConnectionManager,
+    pub redis: Redis,
+}
+
+#[cfg(not(tarpaulin_include))]
+impl AsyncClient {
+    pub async fn try_init(config: Config) -> Result<Self> {
+        let conn_url = Client::generate_conn_url(&config);
+        let redis = Redis::open(conn_url).map_err(Error::Connection)?;
+        let connection = redis.get_tokio_connection_manager().await?;
+
+        Ok(Self {
+            config,
+            connection,
+            redis,
+        })
+    }
+
+    pub async fn try_new() -> Result<Self> {
+        Self::try_init(Config::try_new()?).await
+    }
+
+    pub async fn publish<K, E>(&self, channel: K, message: E) -> Result<()>
+    where
+        K: ToRedisArgs + Send + Sync,
+        E: ToRedisArgs + Send + Sync,
+    {
+        let mut connection = self.connection.clone();
+        Ok(connection.publish(channel, message).await?)
+    }
+
+    /// Subscribes to `channels` on a connection of its own. Pub/sub can't
+    /// share the managed connection, so if this one drops the subscription
+    /// reconnects and resubscribes, backing off for about half a minute like
+    /// a [`Listener`], and the stream ends if Redis doesn't come back.
+    /// Messages published while it was down are lost.
+    pub async fn subscribe<C: ToRedisArgs>(&self, channels: C) -> Result<Subscription> {
+        let channels = channels.to_redis_args();
+        let messages = Subscription::connect(&self.redis, &channels).await?;
+
+        Ok(Subscription::new(self.redis.clone(), channels, messages))
+    }
+}
+
+struct State {
+    redis: Redis,
+    channels: Vec<Vec<u8>>,
+    messages: Option<BoxStream<'static, Msg>>,
+    attempts: u32,
+}
+
+/// A stream of the messages published to a set of channels.
+pub struct Subscription {
+    messages: BoxStream<'static, Msg>,
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Subscription {
+    async fn connect(redis: &Redis, channels: &[Vec<u8>]) -> Result<BoxStream<'static, Msg>> {
+        let mut pubsub = redis.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(channels).await?;
+
+        Ok(pubsub.into_on_message().boxed())
+    }
+
+    fn new(redis: Redis, channels: Vec<Vec<u8>>, messages: BoxStream<'static, Msg>) -> Self {
+        let policy = Listener::reconnect_policy();
+        let state = State {
+            redis,
+            channels,
+            messages: Some(messages),
+            attempts: 0,
+        };
+
+        let messages = stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(messages) = state.messages.as_mut() {
+                    match messages.next().await {
+                        Some(msg) => return Some((msg, state)),
+                        None => state.messages = None,
+                    }
+                }
+
+                state.attempts += 1;
+                tokio::time::sleep(policy.backoff(state.attempts)).await;
+                match Self::connect(&state.redis, &state.channels).await {
+                    Ok(messages) => {
+                        state.messages = Some(messages);
+                        state.attempts = 0;
+                    }
+                    Err(err) if err.is_connection() && policy.should_retry(state.attempts) => {}
+                    Err(_) => return None,
+                }
+            }
+        });
+
+        Self {
+            messages: messages.fuse().boxed(),
+        }
+    }
+
+    /// Decodes each message's payload as a JSON-encoded `T`.
+    pub fn decode<T: DeserializeOwned>(self) -> impl Stream<Item = serde_json::Result<T>> {
+        self.map(|msg| serde_json::from_slice(msg.get_payload_bytes()))
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Msg;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Msg>> {
+        self.messages.poll_next_unpin(cx)
+    }
+}
@@ -0,0 +1,284 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::client::Client;
+use crate::dead_letter::DeadLetter;
+use crate::error::Result;
+
+/// A task waiting in a backup's delayed set, with the member it's stored
+/// as there and the string it moves onto the queue list as.
+pub(crate) struct Delayed<T> {
+    pub member: String,
+    pub raw: String,
+    pub at: SystemTime,
+    pub task: T,
+}
+
+/// Mirrors a worker's pending tasks into a Redis list so a restarted worker
+/// can pick up where it left off. Tasks are removed once they finish
+/// processing, so anything in flight during a crash is replayed.
+pub struct Backup<T> {
+    pub key: String,
+    pub dead_key: String,
+    pub delayed_key: String,
+    client: Arc<Mutex<Client>>,
+    encode: fn(&T) -> serde_json::Result<String>,
+}
+
+impl<T> Clone for Backup<T> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            dead_key: self.dead_key.clone(),
+            delayed_key: self.delayed_key.clone(),
+            client: Arc::clone(&self.client),
+            encode: self.encode,
+        }
+    }
+}
+
+impl<T> Backup<T> {
+    pub fn generate_key(channel: &str) -> String {
+        format!("toretsu:{channel}:queue")
+    }
+
+    pub fn generate_dead_key(channel: &str) -> String {
+        format!("toretsu:{channel}:dead")
+    }
+
+    pub fn generate_delayed_key(channel: &str) -> String {
+        format!("toretsu:{channel}:delayed")
+    }
+
+    pub fn encode(&self, task: &T) -> serde_json::Result<String> {
+        (self.encode)(task)
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl<T> Backup<T> {
+    pub fn new(client: Client, channel: &str) -> Self
+    where
+        T: Serialize,
+    {
+        Self {
+            key: Self::generate_key(channel),
+            dead_key: Self::generate_dead_key(channel),
+            delayed_key: Self::generate_delayed_key(channel),
+            client: Arc::new(Mutex::new(client)),
+            encode: serde_json::to_string::<T>,
+        }
+    }
+
+    pub fn push(&self, task: &T) -> Result<()> {
+        let raw = self.encode(task)?;
+        let mut client = self.client.lock().unwrap();
+        Ok(client.connection.rpush(&self.key, raw)?)
+    }
+
+    pub fn push_many<'a, I: IntoIterator<Item = &'a T>>(&self, tasks: I) -> Result<()>
+    where
+        T: 'a,
+    {
+        let raw = tasks
+            .into_iter()
+            .map(|task| self.encode(task))
+            .collect::<serde_json::Result<Vec<String>>>()?;
+
+        self.push_raw(&raw)
+    }
+
+    pub fn push_raw(&self, raw: &[String]) -> Result<()> {
+        if raw.is_empty() {
+            return Ok(());
+        }
+
+        let mut client = self.client.lock().unwrap();
+        Ok(client.connection.rpush(&self.key, raw)?)
+    }
+
+    pub fn remove(&self, raw: &str) -> Result<()> {
+        let mut client = self.client.lock().unwrap();
+        Ok(client.connection.lrem(&self.key, 1, raw)?)
+    }
+
+    pub fn load(&self) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let tasks = self.load_raw()?.into_iter().map(|(_, task)| task).collect();
+
+        Ok(tasks)
+    }
+
+    /// Loads each task along with the exact string it was stored as, which
+    /// is what [`Backup::remove`] needs to drop it again. Anything that
+    /// can't be decoded as a task is moved to the dead-letter list with the
+    /// reason, rather than fail the whole load.
+    pub fn load_raw(&self) -> Result<Vec<(String, T)>>
+    where
+        T: DeserializeOwned,
+    {
+        let raw: Vec<String> = {
+            let mut client = self.client.lock().unwrap();
+            client.connection.lrange(&self.key, 0, -1)?
+        };
+
+        let mut tasks = Vec::with_capacity(raw.len());
+        for item in raw {
+            match serde_json::from_str(&item) {
+                Ok(task) => tasks.push((item, task)),
+                Err(err) => self.bury(&item, &DeadLetter::<T>::undecodable_record(&item, &err))?,
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        let mut client = self.client.lock().unwrap();
+        Ok(client
+            .connection
+            .del(&[&self.key, &self.dead_key, &self.delayed_key])?)
+    }
+
+    /// Atomically moves a task from the queue list to the dead-letter list.
+    pub fn bury(&self, raw: &str, record: &str) -> Result<()> {
+        let mut client = self.client.lock().unwrap();
+        redis::pipe()
+            .atomic()
+            .lrem(&self.key, 1, raw)
+            .ignore()
+            .rpush(&self.dead_key, record)
+            .ignore()
+            .query::<()>(&mut client.connection)?;
+
+        Ok(())
+    }
+
+    /// Atomically moves a dead letter back onto the queue list.
+    pub fn exhume(&self, record: &str, raw: &str) -> Result<()> {
+        let mut client = self.client.lock().unwrap();
+        redis::pipe()
+            .atomic()
+            .lrem(&self.dead_key, 1, record)
+            .ignore()
+            .rpush(&self.key, raw)
+            .ignore()
+            .query::<()>(&mut client.connection)?;
+
+        Ok(())
+    }
+
+    pub fn forget(&self, record: &str) -> Result<()> {
+        let mut client = self.client.lock().unwrap();
+        Ok(client.connection.lrem(&self.dead_key, 1, record)?)
+    }
+
+    /// Loads the dead letters whose task can be decoded. The rest, such as
+    /// those [`Backup::load_raw`] set aside, stay in the list to be looked
+    /// into by hand.
+    pub fn load_dead(&self) -> Result<Vec<DeadLetter<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let mut client = self.client.lock().unwrap();
+        let records: Vec<String> = client.connection.lrange(&self.dead_key, 0, -1)?;
+        let dead = records
+            .into_iter()
+            .filter_map(|record| DeadLetter::decode_record(record).ok())
+            .collect();
+
+        Ok(dead)
+    }
+
+    /// Holds `raw` in the delayed set until `at`, returning the member it's
+    /// stored as. Members are `{id}:{raw}`, so the same task can be
+    /// scheduled more than once.
+    pub fn delay(&self, raw: &str, at: SystemTime) -> Result<String> {
+        let member = format!("{}:{raw}", Uuid::new_v4());
+        let score = at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut client = self.client.lock().unwrap();
+        let _: usize = client.connection.zadd(&self.delayed_key, &member, score)?;
+
+        Ok(member)
+    }
+
+    /// Atomically moves a delayed task onto the queue list once it's due.
+    pub fn promote(&self, member: &str) -> Result<()> {
+        let raw = match member.split_once(':') {
+            Some((_, raw)) => raw,
+            None => member,
+        };
+
+        let mut client = self.client.lock().unwrap();
+        redis::pipe()
+            .atomic()
+            .zrem(&self.delayed_key, member)
+            .ignore()
+            .rpush(&self.key, raw)
+            .ignore()
+            .query::<()>(&mut client.connection)?;
+
+        Ok(())
+    }
+
+    /// Atomically moves a delayed task to the dead-letter list.
+    fn bury_delayed(&self, member: &str, record: &str) -> Result<()> {
+        let mut client = self.client.lock().unwrap();
+        redis::pipe()
+            .atomic()
+            .zrem(&self.delayed_key, member)
+            .ignore()
+            .rpush(&self.dead_key, record)
+            .ignore()
+            .query::<()>(&mut client.connection)?;
+
+        Ok(())
+    }
+
+    /// Loads the delayed tasks, moving any that can't be decoded to the
+    /// dead-letter list like [`Backup::load_raw`] does.
+    pub(crate) fn load_delayed(&self) -> Result<Vec<Delayed<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let members: Vec<(String, u64)> = {
+            let mut client = self.client.lock().unwrap();
+            client
+                .connection
+                .zrange_withscores(&self.delayed_key, 0, -1)?
+        };
+
+        let mut delayed = Vec::with_capacity(members.len());
+        for (member, score) in members {
+            let raw = match member.split_once(':') {
+                Some((_, raw)) => raw.to_string(),
+                None => member.clone(),
+            };
+            match serde_json::from_str(&raw) {
+                Ok(task) => delayed.push(Delayed {
+                    at: UNIX_EPOCH + Duration::from_millis(score),
+                    member,
+                    raw,
+                    task,
+                }),
+                Err(err) => {
+                    let record = DeadLetter::<T>::undecodable_record(&raw, &err);
+                    self.bury_delayed(&member, &record)?;
+                }
+            }
+        }
+
+        Ok(delayed)
+    }
+}
@@ -0,0 +1,117 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::config::Config;
+use crate::error::Result;
+use crate::listener::Listener;
+
+mod file;
+mod memory;
+mod redis;
+
+pub use file::FileBroker;
+pub use memory::MemoryBroker;
+pub use redis::RedisBroker;
+
+// How long a pull waits on an empty channel before checking whether it's
+// been asked to stop.
+const POLL: Duration = Duration::from_millis(250);
+
+/// Which [`Broker`] [`open`] builds, set with `TORETSU_BROKER`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerKind {
+    /// Kept in the process, and lost with it.
+    Memory,
+    /// Shared through Redis, for any number of processes.
+    Redis,
+    /// Kept in a directory, for a single process on a single machine.
+    File,
+}
+
+/// A task handed out by [`Broker::consume`]. It stays reserved until it's
+/// acknowledged with [`Broker::ack`] or returned with [`Broker::nack`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub id: String,
+    pub channel: String,
+    pub payload: Vec<u8>,
+    /// How many times this message has been handed out, this one included.
+    pub attempts: u32,
+}
+
+/// Carries tasks between whoever publishes them and the workers that run
+/// them. A consumed message is held back from everyone else until it's
+/// acknowledged or returned, so each one goes to a single consumer at a
+/// time; where it lives, and what survives a crash, is up to the backend.
+pub trait Broker: Send + Sync {
+    /// Queues `payload` on `channel`, returning the new message's id.
+    fn publish(&self, channel: &str, payload: &[u8]) -> Result<String>;
+
+    /// Takes the next ready message from `channel`, waiting up to `timeout`
+    /// for one to arrive.
+    fn consume(&self, channel: &str, timeout: Duration) -> Result<Option<Message>>;
+
+    /// Forgets a consumed message for good.
+    fn ack(&self, message: &Message) -> Result<()>;
+
+    /// Returns a consumed message to its channel, to be handed out again
+    /// after `delay`.
+    fn nack(&self, message: &Message, delay: Duration) -> Result<()>;
+
+    /// Queues `payload` on `channel` to be handed out no sooner than `at`.
+    fn schedule(&self, channel: &str, payload: &[u8], at: SystemTime) -> Result<String>;
+}
+
+/// Opens the broker `config` asks for.
+pub fn open(config: &Config) -> Result<Arc<dyn Broker>> {
+    let broker: Arc<dyn Broker> = match config.broker {
+        BrokerKind::Memory => Arc::new(MemoryBroker::new()),
+        BrokerKind::Redis => Arc::new(RedisBroker::new(Client::try_init(config.clone())?)?),
+        BrokerKind::File => Arc::new(FileBroker::open(&config.broker_path)?),
+    };
+
+    Ok(broker)
+}
+
+// Consumes from `channel` into `sink` whenever it has room, until it breaks
+// or the listener is stopped. A lost connection is waited out the way a
+// subscription's is; backends reconnect on their next call.
+fn run<F>(broker: &dyn Broker, channel: &str, stop: &AtomicBool, sink: F) -> Result<()>
+where
+    F: FnMut(Option<Message>) -> ControlFlow<(), usize>,
+{
+    let policy = Listener::reconnect_policy();
+    let mut failures = 0;
+    let fetch = |_| match broker.consume(channel, POLL) {
+        Ok(message) => {
+            failures = 0;
+            Ok(message)
+        }
+        Err(err) if err.is_connection() && policy.should_retry(failures + 1) => {
+            failures += 1;
+            Listener::pause(stop, policy.backoff(failures));
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    };
+
+    Listener::fill(stop, fetch, sink)
+}
+
+/// Consumes from `channel` on a background thread and hands each message to
+/// `sink`, which returns how many more it has room for. Used by
+/// [`Worker::consume_from`](crate::worker::Worker::consume_from).
+pub(crate) fn pull<F>(broker: Arc<dyn Broker>, channel: String, sink: F) -> Listener
+where
+    F: FnMut(Option<Message>) -> ControlFlow<(), usize> + Send + 'static,
+{
+    Listener::start("toretsu-broker", move |stop| {
+        run(broker.as_ref(), &channel, stop, sink)
+    })
+}
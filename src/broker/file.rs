@@ -0,0 +1,237 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use uuid::Uuid;
+
+use super::{Broker, Message};
+use crate::error::Result;
+use crate::scheduler::millis;
+
+// How often an empty channel's directory is checked again while a consume
+// waits.
+const POLL: Duration = Duration::from_millis(50);
+
+// The name of a channel's directory under the root. Anything but ASCII
+// letters, digits, `-` and `_` is percent-encoded, so a channel can't name a
+// path outside its own directory, such as `..` or `orders/eu`. The empty
+// channel gets `%`, which no other channel encodes to.
+fn encode(channel: &str) -> String {
+    if channel.is_empty() {
+        return String::from("%");
+    }
+
+    let mut name = String::with_capacity(channel.len());
+    for byte in channel.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => name.push(byte as char),
+            _ => name.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    name
+}
+
+// A file in a channel's `ready` directory, named
+// `{ready_at}-{sequence}-{attempts}-{id}` so they sort in the order they're
+// handed out.
+struct Ready {
+    name: String,
+    at: u64,
+    attempts: u32,
+    id: String,
+}
+
+impl Ready {
+    fn parse(name: String) -> Option<Self> {
+        let mut parts = name.splitn(4, '-');
+        let at = parts.next()?.parse().ok()?;
+        let _sequence = parts.next()?;
+        let attempts = parts.next()?.parse().ok()?;
+        let id = parts.next()?.to_string();
+
+        Some(Self {
+            name,
+            at,
+            attempts,
+            id,
+        })
+    }
+}
+
+/// A broker that keeps each channel in a directory under `root`, one file
+/// per message, for single-box deployments with nothing else to run. The
+/// directories are named after their channels, percent-encoded so that any
+/// channel name stays inside `root`.
+/// Messages survive a restart: the consuming process calls
+/// [`FileBroker::recover`] as it starts, so a task in flight during a crash
+/// is handed out again. Any number of processes can open the directory to
+/// publish, but only one should consume from it.
+pub struct FileBroker {
+    root: PathBuf,
+    sequence: AtomicU64,
+}
+
+impl FileBroker {
+    /// Opens the broker kept under `root`, creating it if need be.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let broker = Self {
+            root: root.as_ref().to_path_buf(),
+            sequence: AtomicU64::new(0),
+        };
+
+        fs::create_dir_all(&broker.root)?;
+
+        Ok(broker)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Makes every reserved message ready again, returning how many there
+    /// were. Only the consumer should call this, before it starts consuming:
+    /// messages it has reserved since would be handed out twice.
+    pub fn recover(&self) -> Result<usize> {
+        let now = millis(SystemTime::now());
+        let mut recovered = 0;
+        for channel in fs::read_dir(&self.root)? {
+            let channel = channel?.path();
+            let reserved = channel.join("reserved");
+            if !reserved.is_dir() {
+                continue;
+            }
+
+            for file in fs::read_dir(&reserved)? {
+                let name = file?.file_name().to_string_lossy().into_owned();
+                if let Some((attempts, id)) = name.split_once('-') {
+                    let target = self.ready_file(&channel.join("ready"), now, attempts, id);
+                    fs::rename(reserved.join(&name), target)?;
+                    recovered += 1;
+                }
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    fn dir(&self, channel: &str, state: &str) -> PathBuf {
+        self.root.join(encode(channel)).join(state)
+    }
+
+    fn ready_path(&self, channel: &str, at: u64, attempts: impl ToString, id: &str) -> PathBuf {
+        self.ready_file(&self.dir(channel, "ready"), at, attempts, id)
+    }
+
+    fn ready_file(&self, ready: &Path, at: u64, attempts: impl ToString, id: &str) -> PathBuf {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let attempts = attempts.to_string();
+
+        ready.join(format!("{at:020}-{sequence:020}-{attempts}-{id}"))
+    }
+
+    fn reserved_path(&self, message: &Message) -> PathBuf {
+        self.dir(&message.channel, "reserved")
+            .join(format!("{}-{}", message.attempts, message.id))
+    }
+
+    // Writes the message out of sight first, so a consumer never sees half
+    // of it.
+    fn write(&self, channel: &str, payload: &[u8], at: SystemTime) -> Result<String> {
+        for state in ["tmp", "ready", "reserved"] {
+            fs::create_dir_all(self.dir(channel, state))?;
+        }
+
+        let id = Uuid::new_v4().simple().to_string();
+        let tmp = self.dir(channel, "tmp").join(&id);
+        fs::write(&tmp, payload)?;
+        fs::rename(&tmp, self.ready_path(channel, millis(at), 0, &id))?;
+
+        Ok(id)
+    }
+
+    // Reserves the first message that's ready, if any is.
+    fn take(&self, channel: &str) -> Result<Option<Message>> {
+        let ready = self.dir(channel, "ready");
+        let mut files: Vec<Ready> = match fs::read_dir(&ready) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| Ready::parse(entry.file_name().to_string_lossy().into_owned()))
+                .collect(),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        files.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+        let now = millis(SystemTime::now());
+        for file in files {
+            if file.at > now {
+                break;
+            }
+
+            let message = Message {
+                id: file.id,
+                channel: channel.to_string(),
+                payload: Vec::new(),
+                attempts: file.attempts + 1,
+            };
+            let reserved = self.reserved_path(&message);
+            match fs::rename(ready.join(&file.name), &reserved) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            }
+
+            let payload = fs::read(&reserved)?;
+            return Ok(Some(Message { payload, ..message }));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Broker for FileBroker {
+    fn publish(&self, channel: &str, payload: &[u8]) -> Result<String> {
+        self.write(channel, payload, SystemTime::now())
+    }
+
+    fn consume(&self, channel: &str, timeout: Duration) -> Result<Option<Message>> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(message) = self.take(channel)? {
+                return Ok(Some(message));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            std::thread::sleep((deadline - now).min(POLL));
+        }
+    }
+
+    fn ack(&self, message: &Message) -> Result<()> {
+        match fs::remove_file(self.reserved_path(message)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn nack(&self, message: &Message, delay: Duration) -> Result<()> {
+        let at = millis(SystemTime::now() + delay);
+        let target = self.ready_path(&message.channel, at, message.attempts, &message.id);
+
+        match fs::rename(self.reserved_path(message), target) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn schedule(&self, channel: &str, payload: &[u8], at: SystemTime) -> Result<String> {
+        self.write(channel, payload, at)
+    }
+}
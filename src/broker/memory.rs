@@ -0,0 +1,193 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use uuid::Uuid;
+
+use super::{Broker, Message};
+use crate::error::Result;
+
+// A message waiting for `at` before it's ready. Ordered so the earliest is
+// at the top of a `BinaryHeap`, with ties going first come, first served.
+struct Scheduled {
+    at: SystemTime,
+    sequence: u64,
+    message: Message,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .at
+            .cmp(&self.at)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Channel {
+    ready: VecDeque<Message>,
+    scheduled: BinaryHeap<Scheduled>,
+    reserved: HashMap<String, Message>,
+}
+
+impl Channel {
+    // Moves every scheduled message that's come due to the back of the
+    // ready queue, returning when the next one will.
+    fn promote(&mut self, now: SystemTime) -> Option<SystemTime> {
+        while let Some(next) = self.scheduled.peek() {
+            if next.at > now {
+                return Some(next.at);
+            }
+
+            let due = self.scheduled.pop().unwrap();
+            self.ready.push_back(due.message);
+        }
+
+        None
+    }
+}
+
+#[derive(Default)]
+struct State {
+    channels: HashMap<String, Channel>,
+    sequence: u64,
+}
+
+impl State {
+    fn push(&mut self, message: Message, at: SystemTime) {
+        self.sequence += 1;
+        let sequence = self.sequence;
+        let channel = self.channels.entry(message.channel.clone()).or_default();
+
+        match at <= SystemTime::now() {
+            true => channel.ready.push_back(message),
+            false => channel.scheduled.push(Scheduled {
+                at,
+                sequence,
+                message,
+            }),
+        }
+    }
+}
+
+/// A broker that keeps everything in the process. Messages are lost when it
+/// exits, which makes it a fit for tests and for producers and workers that
+/// live side by side.
+#[derive(Default)]
+pub struct MemoryBroker {
+    state: Mutex<State>,
+    ready: Condvar,
+}
+
+impl MemoryBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many messages `channel` holds, ready, scheduled or reserved.
+    pub fn len(&self, channel: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state.channels.get(channel).map_or(0, |channel| {
+            channel.ready.len() + channel.scheduled.len() + channel.reserved.len()
+        })
+    }
+
+    pub fn is_empty(&self, channel: &str) -> bool {
+        self.len(channel) == 0
+    }
+
+    fn enqueue(&self, channel: &str, payload: &[u8], at: SystemTime) -> String {
+        let message = Message {
+            id: Uuid::new_v4().to_string(),
+            channel: channel.to_string(),
+            payload: payload.to_vec(),
+            attempts: 0,
+        };
+        let id = message.id.clone();
+
+        self.state.lock().unwrap().push(message, at);
+        self.ready.notify_all();
+
+        id
+    }
+}
+
+impl Broker for MemoryBroker {
+    fn publish(&self, channel: &str, payload: &[u8]) -> Result<String> {
+        Ok(self.enqueue(channel, payload, SystemTime::now()))
+    }
+
+    fn consume(&self, channel: &str, timeout: Duration) -> Result<Option<Message>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            let queue = state.channels.entry(channel.to_string()).or_default();
+            let next = queue.promote(SystemTime::now());
+
+            if let Some(mut message) = queue.ready.pop_front() {
+                message.attempts += 1;
+                queue.reserved.insert(message.id.clone(), message.clone());
+                return Ok(Some(message));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            let mut wait = deadline - now;
+            if let Some(next) = next {
+                let until = next.duration_since(SystemTime::now()).unwrap_or_default();
+                wait = wait.min(until);
+            }
+
+            state = self.ready.wait_timeout(state, wait).unwrap().0;
+        }
+    }
+
+    fn ack(&self, message: &Message) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(channel) = state.channels.get_mut(&message.channel) {
+            channel.reserved.remove(&message.id);
+        }
+
+        Ok(())
+    }
+
+    fn nack(&self, message: &Message, delay: Duration) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let reserved = state
+            .channels
+            .get_mut(&message.channel)
+            .and_then(|channel| channel.reserved.remove(&message.id));
+
+        if let Some(reserved) = reserved {
+            state.push(reserved, SystemTime::now() + delay);
+            drop(state);
+            self.ready.notify_all();
+        }
+
+        Ok(())
+    }
+
+    fn schedule(&self, channel: &str, payload: &[u8], at: SystemTime) -> Result<String> {
+        Ok(self.enqueue(channel, payload, at))
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use ::redis::streams::{
+    StreamId, StreamPendingCountReply, StreamRangeReply, StreamReadOptions, StreamReadReply,
+};
+use ::redis::{Commands, Connection, Script, Value};
+use uuid::Uuid;
+
+use super::{Broker, Message};
+use crate::client::Client;
+use crate::error::Result;
+use crate::scheduler::millis;
+use crate::streams::{ConsumerGroup, FIELD};
+
+// The stream field holding how many times a returned message was handed out
+// before.
+const ATTEMPTS: &str = "attempts";
+
+// How long a read blocks before promoting scheduled messages again.
+const POLL: Duration = Duration::from_millis(250);
+
+// Moves scheduled messages that have come due onto the stream. Members are
+// `{id}:{attempts}:{payload}`, where the id is a 36-character uuid.
+const PROMOTE: &str = r"
+local due = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', ARGV[1], 'LIMIT', 0, 100)
+for _, member in ipairs(due) do
+    redis.call('ZREM', KEYS[2], member)
+    local split = string.find(member, ':', 38, true)
+    redis.call('XADD', KEYS[1], '*',
+        ARGV[2], string.sub(member, split + 1),
+        ARGV[3], string.sub(member, 38, split - 1))
+end
+return #due
+";
+
+/// A broker on top of Redis streams, for any number of processes. Each
+/// channel is the stream [`Client::send`] adds to, read by a consumer group
+/// that every `RedisBroker` with the same group name shares. A message that
+/// goes unacknowledged for longer than `min_idle` - because its consumer
+/// died, say - is handed out again.
+///
+/// Scheduled and returned messages wait in a sorted set and move onto the
+/// stream once due.
+pub struct RedisBroker {
+    group: ConsumerGroup,
+    consumer: String,
+    writer: Mutex<Client>,
+    reader: Mutex<Client>,
+    promote: Script,
+    groups: Mutex<HashSet<String>>,
+    // When each channel last had nothing left to claim.
+    claimed_at: Mutex<HashMap<String, Instant>>,
+}
+
+impl RedisBroker {
+    pub fn generate_scheduled_key(channel: &str) -> String {
+        format!("toretsu:{channel}:scheduled")
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl RedisBroker {
+    /// Uses `client` to write and opens a second connection to read, with
+    /// the consumer group named `toretsu`.
+    pub fn new(client: Client) -> Result<Self> {
+        let reader = Client::try_init(client.config.clone())?;
+
+        Ok(Self {
+            group: ConsumerGroup::new("toretsu"),
+            consumer: Uuid::new_v4().to_string(),
+            writer: Mutex::new(client),
+            reader: Mutex::new(reader),
+            promote: Script::new(PROMOTE),
+            groups: Mutex::new(HashSet::new()),
+            claimed_at: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Reads as a member of `group`. Its `batch` is ignored: messages are
+    /// consumed one at a time.
+    pub fn with_group(mut self, group: ConsumerGroup) -> Self {
+        self.group = group;
+        self
+    }
+
+    // Runs `command` on `client`'s connection, replacing the connection if
+    // it turns out to be lost so the next call can succeed.
+    fn with<R, F>(client: &Mutex<Client>, command: F) -> Result<R>
+    where
+        F: FnOnce(&mut Connection) -> Result<R>,
+    {
+        let mut client = client.lock().unwrap();
+        let result = command(&mut client.connection);
+        if let Err(err) = &result {
+            if err.is_connection() {
+                if let Ok(connection) = client.redis.get_connection() {
+                    client.connection = connection;
+                }
+            }
+        }
+
+        result
+    }
+
+    fn create_group(&self, connection: &mut Connection, key: &str) -> Result<()> {
+        if self.groups.lock().unwrap().contains(key) {
+            return Ok(());
+        }
+
+        let created: ::redis::RedisResult<()> =
+            connection.xgroup_create_mkstream(key, &self.group.name, "0");
+        match created {
+            Err(err) if err.code() != Some("BUSYGROUP") => return Err(err.into()),
+            _ => {}
+        }
+
+        self.groups.lock().unwrap().insert(key.to_string());
+        Ok(())
+    }
+
+    fn promote(&self, connection: &mut Connection, channel: &str) -> Result<()> {
+        let _: usize = self
+            .promote
+            .key(ConsumerGroup::generate_key(channel))
+            .key(Self::generate_scheduled_key(channel))
+            .arg(millis(SystemTime::now()))
+            .arg(FIELD)
+            .arg(ATTEMPTS)
+            .invoke(connection)?;
+
+        Ok(())
+    }
+
+    // Turns a stream entry handed out `delivered` times into a message.
+    // Entries without a task are acknowledged and skipped.
+    fn message(
+        &self,
+        connection: &mut Connection,
+        channel: &str,
+        entry: StreamId,
+        delivered: u32,
+    ) -> Result<Option<Message>> {
+        let key = ConsumerGroup::generate_key(channel);
+        let payload = match entry.map.get(FIELD) {
+            Some(Value::Data(payload)) => payload.clone(),
+            _ => {
+                let _: usize = connection.xack(&key, &self.group.name, &[&entry.id])?;
+                return Ok(None);
+            }
+        };
+        let returned: u32 = entry.get(ATTEMPTS).unwrap_or(0);
+
+        Ok(Some(Message {
+            id: entry.id,
+            channel: channel.to_string(),
+            payload,
+            attempts: returned + delivered.max(1),
+        }))
+    }
+
+    // Takes over a message another consumer has left pending for longer
+    // than `min_idle`. Once there's none left, the channel isn't checked
+    // again until `min_idle` has passed.
+    fn claim(&self, connection: &mut Connection, channel: &str) -> Result<Option<Message>> {
+        let due = self
+            .claimed_at
+            .lock()
+            .unwrap()
+            .get(channel)
+            .is_none_or(|at| at.elapsed() >= self.group.min_idle);
+        if !due {
+            return Ok(None);
+        }
+
+        let key = ConsumerGroup::generate_key(channel);
+        let reply: Vec<Value> = ::redis::cmd("XAUTOCLAIM")
+            .arg(&key)
+            .arg(&self.group.name)
+            .arg(&self.consumer)
+            .arg(self.group.min_idle.as_millis() as u64)
+            .arg("0-0")
+            .arg("COUNT")
+            .arg(1)
+            .query(connection)?;
+        let claimed: StreamRangeReply = match reply.get(1) {
+            Some(value) => ::redis::from_redis_value(value)?,
+            None => StreamRangeReply::default(),
+        };
+
+        let entry = match claimed.ids.into_iter().next() {
+            Some(entry) => entry,
+            None => {
+                let mut claimed_at = self.claimed_at.lock().unwrap();
+                claimed_at.insert(channel.to_string(), Instant::now());
+                return Ok(None);
+            }
+        };
+
+        let pending: StreamPendingCountReply =
+            connection.xpending_count(&key, &self.group.name, &entry.id, &entry.id, 1)?;
+        let delivered = pending
+            .ids
+            .first()
+            .map_or(1, |pending| pending.times_delivered as u32);
+
+        self.message(connection, channel, entry, delivered)
+    }
+
+    fn read(
+        &self,
+        connection: &mut Connection,
+        channel: &str,
+        block: Duration,
+    ) -> Result<Option<Message>> {
+        let mut options = StreamReadOptions::default()
+            .group(&self.group.name, &self.consumer)
+            .count(1);
+        if !block.is_zero() {
+            options = options.block(block.as_millis().max(1) as usize);
+        }
+
+        let key = ConsumerGroup::generate_key(channel);
+        let reply: Option<StreamReadReply> = connection.xread_options(&[&key], &[">"], &options)?;
+        let entry = reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .next();
+
+        match entry {
+            Some(entry) => self.message(connection, channel, entry, 1),
+            None => Ok(None),
+        }
+    }
+
+    // Puts a payload in the scheduled set, to move onto the stream at `at`,
+    // returning the id it's kept under until then.
+    fn defer(
+        pipe: &mut ::redis::Pipeline,
+        channel: &str,
+        payload: &[u8],
+        attempts: u32,
+        at: SystemTime,
+    ) -> String {
+        let id = Uuid::new_v4().to_string();
+        let mut member = format!("{id}:{attempts}:").into_bytes();
+        member.extend_from_slice(payload);
+
+        let key = Self::generate_scheduled_key(channel);
+        pipe.zadd(key, member, millis(at)).ignore();
+
+        id
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Broker for RedisBroker {
+    fn publish(&self, channel: &str, payload: &[u8]) -> Result<String> {
+        let key = ConsumerGroup::generate_key(channel);
+        Self::with(&self.writer, |connection| {
+            Ok(connection.xadd(&key, "*", &[(FIELD, payload)])?)
+        })
+    }
+
+    fn consume(&self, channel: &str, timeout: Duration) -> Result<Option<Message>> {
+        let deadline = Instant::now() + timeout;
+        let key = ConsumerGroup::generate_key(channel);
+
+        Self::with(&self.reader, |connection| {
+            self.create_group(connection, &key)?;
+            if let Some(message) = self.claim(connection, channel)? {
+                return Ok(Some(message));
+            }
+
+            loop {
+                self.promote(connection, channel)?;
+
+                let block = deadline.saturating_duration_since(Instant::now()).min(POLL);
+                if let Some(message) = self.read(connection, channel, block)? {
+                    return Ok(Some(message));
+                }
+
+                if Instant::now() >= deadline {
+                    return Ok(None);
+                }
+            }
+        })
+        .inspect_err(|_| {
+            // The stream may have been deleted, so check on the group next
+            // time round.
+            self.groups.lock().unwrap().remove(&key);
+        })
+    }
+
+    fn ack(&self, message: &Message) -> Result<()> {
+        let key = ConsumerGroup::generate_key(&message.channel);
+        Self::with(&self.writer, |connection| {
+            Ok(::redis::pipe()
+                .atomic()
+                .xack(&key, &self.group.name, &[&message.id])
+                .ignore()
+                .xdel(&key, &[&message.id])
+                .ignore()
+                .query(connection)?)
+        })
+    }
+
+    fn nack(&self, message: &Message, delay: Duration) -> Result<()> {
+        let key = ConsumerGroup::generate_key(&message.channel);
+        let mut pipe = ::redis::pipe();
+        pipe.atomic()
+            .xack(&key, &self.group.name, &[&message.id])
+            .ignore()
+            .xdel(&key, &[&message.id])
+            .ignore();
+        match delay.is_zero() {
+            true => {
+                let attempts = message.attempts.to_string();
+                let fields: [(&str, &[u8]); 2] =
+                    [(FIELD, &message.payload), (ATTEMPTS, attempts.as_bytes())];
+                pipe.xadd(&key, "*", &fields).ignore();
+            }
+            false => {
+                let at = SystemTime::now() + delay;
+                Self::defer(
+                    &mut pipe,
+                    &message.channel,
+                    &message.payload,
+                    message.attempts,
+                    at,
+                );
+            }
+        }
+
+        Self::with(&self.writer, |connection| Ok(pipe.query(connection)?))
+    }
+
+    fn schedule(&self, channel: &str, payload: &[u8], at: SystemTime) -> Result<String> {
+        if at <= SystemTime::now() {
+            return self.publish(channel, payload);
+        }
+
+        let mut pipe = ::redis::pipe();
+        let id = Self::defer(&mut pipe, channel, payload, 0, at);
+        Self::with(&self.writer, |connection| Ok(pipe.query::<()>(connection)?))?;
+
+        Ok(id)
+    }
+}
@@ -0,0 +1,282 @@
+use std::process::ExitCode;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::broker::{self, Broker, BrokerKind, FileBroker};
+use crate::client::Client;
+use crate::config::Config;
+use crate::control;
+use crate::error;
+use crate::listener::Listener;
+use crate::registry::{Parcel, Registry};
+use crate::router::Router;
+use crate::worker::Worker;
+
+pub const NO_KINDS: &str = "\
+No task kinds are registered, so there is nothing for a worker to run.
+Install with `--features exec` to run programs sent as the `exec` kind, or
+build a binary of your own that registers your task kinds and calls
+`toretsu::cli::run`; see the README.";
+
+pub const USAGE: &str = "\
+Usage:
+    toretsu worker [channel]    start a single worker, optionally on a fixed channel
+    toretsu workers <count>     start <count> workers on generated channels
+    toretsu help                print this message
+
+Workers only run the task kinds registered with the binary; see the README.";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Worker(Option<String>),
+    Workers(usize),
+    Help,
+}
+
+impl Command {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let command = args.next();
+        let value = args.next();
+        if let Some(extra) = args.next() {
+            return Err(format!("unexpected argument `{extra}`"));
+        }
+
+        match (command.as_deref(), value) {
+            (Some("worker"), channel) => Ok(Self::Worker(channel)),
+            (Some("workers"), Some(count)) => match count.parse::<usize>() {
+                Ok(count) if count > 0 => Ok(Self::Workers(count)),
+                _ => Err(format!("`{count}` is not a valid number of workers")),
+            },
+            (Some("workers"), None) => Err(String::from("missing number of workers")),
+            (Some("help" | "--help" | "-h"), Some(extra)) => {
+                Err(format!("unexpected argument `{extra}`"))
+            }
+            (Some("help" | "--help" | "-h") | None, _) => Ok(Self::Help),
+            (Some(other), _) => Err(format!("unknown command `{other}`")),
+        }
+    }
+
+    pub fn channels(self) -> Vec<Option<String>> {
+        match self {
+            Self::Worker(channel) => vec![channel],
+            Self::Workers(count) => vec![None; count],
+            Self::Help => Vec::new(),
+        }
+    }
+}
+
+/// Runs the command line with the given task kinds, so a host crate can ship
+/// its own `toretsu` binary with a two-line `main`. Refuses to start workers
+/// when `registry` is empty, as they would drop every task they're sent.
+#[cfg(not(tarpaulin_include))]
+pub fn run(registry: Registry) -> ExitCode {
+    let command = match Command::parse(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if registry.is_empty() {
+        eprintln!("{NO_KINDS}");
+        return ExitCode::FAILURE;
+    }
+    let mut kinds: Vec<&str> = registry.kinds().collect();
+    kinds.sort_unstable();
+    println!("Running task kinds: {}", kinds.join(", "));
+
+    let (tx, rx) = mpsc::channel();
+    if let Err(err) = ctrlc::set_handler(move || {
+        let _ = tx.send(());
+    }) {
+        eprintln!("Failed to install signal handler: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    let config = match Config::try_new() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut workers: Vec<Worker<Parcel>> = command
+        .channels()
+        .into_iter()
+        .map(|channel| Worker::init(None, channel, None))
+        .collect();
+
+    let pubsub = config.pubsub && config.broker == BrokerKind::Redis;
+    let listeners = match config.broker {
+        BrokerKind::Memory => {
+            eprintln!(
+                "The memory broker only carries tasks within one process; use `redis` or `file`."
+            );
+            return ExitCode::FAILURE;
+        }
+        _ if pubsub => subscribe(&config, &registry, &workers).map(|listener| vec![listener]),
+        _ => consume(&config, &registry, &mut workers),
+    };
+    let mut listeners = match listeners {
+        Ok(listeners) => listeners,
+        Err(err) => {
+            eprintln!("Failed to subscribe: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Over Pub/Sub the workers already listen for commands. Otherwise they
+    // get a subscription of their own, which the file broker can do without.
+    let mut control = None;
+    if !pubsub {
+        match Client::try_init(config.clone()).and_then(|client| client.route(controls(&workers))) {
+            Ok(listener) if config.broker == BrokerKind::File => control = Some(listener),
+            Ok(listener) => listeners.push(listener),
+            Err(err) if config.broker == BrokerKind::File => {
+                eprintln!("Can't be paused or resumed remotely: {err}");
+            }
+            Err(err) => {
+                eprintln!("Failed to subscribe: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    for worker in workers.iter_mut() {
+        worker.clock_in();
+        println!("{} {}", worker.channel, worker.id);
+    }
+
+    // Wait for a signal, or for a listener to give up on its broker.
+    while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(Duration::from_secs(1)) {
+        if listeners.iter().any(Listener::is_finished) {
+            break;
+        }
+    }
+
+    for worker in workers.iter_mut() {
+        worker.clock_out();
+    }
+
+    let mut code = ExitCode::SUCCESS;
+    for listener in listeners.into_iter().chain(control) {
+        if let Err(err) = listener.shutdown() {
+            eprintln!("Lost subscription: {err}");
+            code = ExitCode::FAILURE;
+        }
+    }
+
+    // The workers share one deadline, rather than each getting its own.
+    let until = Instant::now() + Duration::from_secs(config.shutdown_timeout);
+    for worker in workers.iter_mut() {
+        let report = worker.shutdown(until.saturating_duration_since(Instant::now()));
+        println!(
+            "Stopped {}: {} finished, {} interrupted, {} never started",
+            worker.channel,
+            report.finished,
+            report.interrupted.len(),
+            report.pending.len()
+        );
+        for id in &report.interrupted {
+            eprintln!("Interrupted {id} on {}", worker.channel);
+        }
+        // Tasks from a broker are still unacknowledged in it, and handed out
+        // again, but ones that came over Pub/Sub are gone.
+        if pubsub {
+            for entry in &report.pending {
+                eprintln!("Dropped {} on {}", entry.id, worker.channel);
+            }
+        }
+        if !report.is_clean() {
+            code = ExitCode::FAILURE;
+        }
+    }
+
+    code
+}
+
+// Every worker shares one Pub/Sub subscription, for its tasks and commands
+// alike.
+#[cfg(not(tarpaulin_include))]
+fn subscribe(
+    config: &Config,
+    registry: &Registry,
+    workers: &[Worker<Parcel>],
+) -> error::Result<Listener> {
+    let client = Client::try_init(config.clone())?;
+    let mut router = controls(workers);
+    for worker in workers {
+        let registry = registry.clone();
+        let handler = worker.handler(move |msg| match registry.decode_msg(msg) {
+            Ok(parcel) => Some(parcel),
+            Err(err) => {
+                eprintln!("Dropped message on {}: {err}", msg.get_channel_name());
+                None
+            }
+        });
+        router.route(&worker.channel, handler);
+    }
+
+    client.route(router)
+}
+
+// Pauses and resumes each worker on the commands sent to its channel.
+#[cfg(not(tarpaulin_include))]
+fn controls(workers: &[Worker<Parcel>]) -> Router {
+    let mut router = Router::new();
+    for worker in workers {
+        router.route(
+            &control::Command::generate_key(&worker.channel),
+            worker.control_handler(),
+        );
+    }
+
+    router
+}
+
+// Each worker pulls from its own channel of the configured broker, holding no
+// more tasks than it has threads to run them on.
+#[cfg(not(tarpaulin_include))]
+fn consume(
+    config: &Config,
+    registry: &Registry,
+    workers: &mut [Worker<Parcel>],
+) -> error::Result<Vec<Listener>> {
+    // This is the file broker's only consumer, so it hands out again what
+    // was in flight when the last one stopped.
+    let broker: Arc<dyn Broker> = match config.broker {
+        BrokerKind::File => {
+            let broker = FileBroker::open(&config.broker_path)?;
+            broker.recover()?;
+            Arc::new(broker)
+        }
+        _ => broker::open(config)?,
+    };
+    let listeners = workers
+        .iter_mut()
+        .map(|worker| {
+            let registry = registry.clone();
+            let channel = worker.channel.clone();
+            let batch = worker.threads();
+            worker.consume_from_with(Arc::clone(&broker), batch, move |raw| {
+                match registry.decode_slice(raw) {
+                    Ok(parcel) => Some(parcel),
+                    Err(err) => {
+                        eprintln!("Dropped message on {channel}: {err}");
+                        None
+                    }
+                }
+            })
+        })
+        .collect();
+
+    Ok(listeners)
+}
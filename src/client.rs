@@ -2,10 +2,18 @@ use std::time::Duration;
 
 use redis::{
     Client as Redis, Commands, Connection, ConnectionInfo, ConnectionLike, ControlFlow, Msg,
-    RedisResult, ToRedisArgs,
+    ToRedisArgs,
 };
 
+use redis::streams::StreamPendingReply;
+use serde::Serialize;
+
 use crate::config::Config;
+use crate::control::Command;
+use crate::error::{Error, Result};
+use crate::listener::{Listener, Topics};
+use crate::router::Router;
+use crate::streams::{ConsumerGroup, FIELD};
 
 pub struct Client {
     pub config: Config,
@@ -52,20 +60,36 @@ impl Client {
         }
     }
 
-    pub fn new() -> Self {
-        let config = Config::new();
+    pub fn try_init(config: Config) -> Result<Self> {
         let timeout = Duration::from_secs(30);
         let conn_url = Self::generate_conn_url(&config);
-        let redis = Redis::open(conn_url).expect("Failed to Locate Redis");
-        let connection = redis
-            .get_connection_with_timeout(timeout)
-            .expect("Failed to Connect to Redis");
+        let redis = Redis::open(conn_url).map_err(Error::Connection)?;
+        let connection = redis.get_connection_with_timeout(timeout)?;
 
-        Self {
+        Ok(Self {
             config,
             connection,
             redis,
-        }
+        })
+    }
+
+    pub fn try_new() -> Result<Self> {
+        Self::try_init(Config::try_new()?)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if Redis can't be reached; see [`Client::try_init`].
+    pub fn init(config: Config) -> Self {
+        Self::try_init(config).expect("Failed to Connect to Redis")
+    }
+
+    /// # Panics
+    ///
+    /// Panics if no configuration is found or Redis can't be reached; see
+    /// [`Client::try_new`].
+    pub fn new() -> Self {
+        Self::try_new().expect("Failed to Connect to Redis")
     }
 
     pub fn check_connection(&mut self) -> bool {
@@ -76,57 +100,99 @@ impl Client {
         self.redis.get_connection_info()
     }
 
-    pub fn set_read_timeout(&mut self, timeout: Duration) -> RedisResult<()> {
-        self.connection.set_read_timeout(Some(timeout))
+    pub fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        Ok(self.connection.set_read_timeout(Some(timeout))?)
     }
 
     pub fn publish<K: ToRedisArgs, E: ToRedisArgs>(
         &mut self,
         channel: K,
         message: E,
-    ) -> RedisResult<()> {
-        self.connection.publish(channel, message)
+    ) -> Result<()> {
+        Ok(self.connection.publish(channel, message)?)
     }
 
-    pub fn subscribe<T: ToRedisArgs>(&mut self, channel: T) -> RedisResult<()> {
+    pub fn subscribe<T: ToRedisArgs>(&mut self, channel: T) -> Result<()> {
         let mut pubsub = self.connection.as_pubsub();
-        pubsub.subscribe(channel)
+        Ok(pubsub.subscribe(channel)?)
     }
 
-    pub fn get_message(&mut self) -> RedisResult<Msg> {
+    pub fn get_message(&mut self) -> Result<Msg> {
         let mut pubsub = self.connection.as_pubsub();
-        pubsub.get_message()
+        Ok(pubsub.get_message()?)
     }
 
-    pub fn listen<C, F, U>(&mut self, channels: C, mut func: F)
+    /// Subscribes to `channels` on a connection of its own and hands each
+    /// message to `func` on a dedicated thread, leaving this client free for
+    /// other commands and the worker pools free for tasks. The subscription
+    /// survives Redis restarts, and runs until `func` breaks or the returned
+    /// [`Listener`] is stopped.
+    pub fn listen<C, F, U>(&self, channels: C, func: F) -> Result<Listener>
     where
-        C: ToRedisArgs + std::marker::Send + 'static,
+        C: ToRedisArgs,
         F: FnMut(Msg) -> ControlFlow<U> + std::marker::Send + 'static,
     {
-        let mut client = std::mem::take(self);
-        rayon::spawn(move || {
-            let _ = client.subscribe(channels);
-            loop {
-                let response = match &client.connection.recv_response() {
-                    Ok(res) => Msg::from_value(res),
-                    Err(_) => continue,
-                };
-
-                let msg = match response {
-                    Some(msg) => msg,
-                    None => continue,
-                };
-
-                match func(msg) {
-                    ControlFlow::Continue => continue,
-                    ControlFlow::Break(_) => break,
-                }
-            }
-        });
+        let topics = Topics {
+            channels: channels.to_redis_args(),
+            patterns: Vec::new(),
+        };
+
+        Listener::spawn(self.redis.clone(), topics, func)
     }
 
-    pub fn unsubscribe<T: ToRedisArgs>(&mut self, channel: T) -> RedisResult<()> {
+    /// Subscribes to every channel and pattern `router` has a route for on
+    /// one connection, and dispatches each message to its handler. Otherwise
+    /// behaves like [`Client::listen`].
+    pub fn route(&self, mut router: Router) -> Result<Listener> {
+        let topics = Topics {
+            channels: router.channels().map(|channel| channel.into()).collect(),
+            patterns: router.patterns().map(|pattern| pattern.into()).collect(),
+        };
+
+        Listener::spawn(self.redis.clone(), topics, move |msg| router.dispatch(&msg))
+    }
+
+    pub fn unsubscribe<T: ToRedisArgs>(&mut self, channel: T) -> Result<()> {
+        let mut pubsub = self.connection.as_pubsub();
+        Ok(pubsub.unsubscribe(channel)?)
+    }
+
+    /// Subscribes to every channel matching a glob-style pattern, such as
+    /// `orders.*`.
+    pub fn psubscribe<T: ToRedisArgs>(&mut self, pattern: T) -> Result<()> {
         let mut pubsub = self.connection.as_pubsub();
-        pubsub.unsubscribe(channel)
+        Ok(pubsub.psubscribe(pattern)?)
+    }
+
+    pub fn punsubscribe<T: ToRedisArgs>(&mut self, pattern: T) -> Result<()> {
+        let mut pubsub = self.connection.as_pubsub();
+        Ok(pubsub.punsubscribe(pattern)?)
+    }
+
+    /// Appends a JSON-encoded task to `channel`'s stream, where it waits for
+    /// a worker in a consumer group to process and acknowledge it. Returns
+    /// the entry's stream id.
+    pub fn send<T: Serialize>(&mut self, channel: &str, task: &T) -> Result<String> {
+        let raw = serde_json::to_string(task)?;
+        let key = ConsumerGroup::generate_key(channel);
+        let id = self.connection.xadd(key, "*", &[(FIELD, raw)])?;
+
+        Ok(id)
+    }
+
+    /// Tells every worker on `channel` that listens with
+    /// [`Worker::control`](crate::worker::Worker::control) to pause or
+    /// resume.
+    pub fn control(&mut self, channel: &str, command: Command) -> Result<()> {
+        self.publish(Command::generate_key(channel), command.as_str())
+    }
+
+    /// How many tasks on `channel`'s stream `group` has handed out but not
+    /// yet had acknowledged.
+    pub fn pending(&mut self, channel: &str, group: &str) -> Result<usize> {
+        let key = ConsumerGroup::generate_key(channel);
+        let reply: StreamPendingReply = self.connection.xpending(key, group)?;
+
+        Ok(reply.count())
     }
 }
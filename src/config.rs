@@ -1,6 +1,9 @@
 use confique::Config as Configure;
 use dotenv::dotenv;
 
+use crate::broker::BrokerKind;
+use crate::error::Result;
+
 #[derive(Configure, Clone)]
 pub struct Config {
     #[config(env = "REDIS_HOST", default = "127.0.0.1")]
@@ -17,6 +20,25 @@ pub struct Config {
 
     #[config(env = "REDIS_DATABASE")]
     pub redis_db: Option<String>,
+
+    /// Which broker carries tasks: `redis`, `memory` or `file`.
+    #[config(env = "TORETSU_BROKER", default = "redis")]
+    pub broker: BrokerKind,
+
+    /// Where the `file` broker keeps its messages.
+    #[config(env = "TORETSU_BROKER_PATH", default = "toretsu-data")]
+    pub broker_path: String,
+
+    /// Has the `toretsu` binary subscribe to its channels over Pub/Sub with
+    /// the `redis` broker, rather than read their streams. Pub/Sub loses
+    /// whatever is published while no worker is listening.
+    #[config(env = "TORETSU_PUBSUB", default = false)]
+    pub pubsub: bool,
+
+    /// How many seconds the `toretsu` binary waits for running tasks when
+    /// it's stopped, before cancelling them.
+    #[config(env = "TORETSU_SHUTDOWN_TIMEOUT", default = 30)]
+    pub shutdown_timeout: u64,
 }
 
 impl Default for Config {
@@ -26,15 +48,23 @@ impl Default for Config {
 }
 
 impl Config {
-    pub fn new() -> Self {
+    pub fn try_new() -> Result<Self> {
         dotenv().ok();
 
-        Self::builder()
+        let config = Self::builder()
             .env()
             .file("toretsu.json")
             .file("toretsu.yaml")
             .file("toretsu.toml")
-            .load()
-            .expect("Config Not Found")
+            .load()?;
+
+        Ok(config)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the configuration can't be loaded; see [`Config::try_new`].
+    pub fn new() -> Self {
+        Self::try_new().expect("Config Not Found")
     }
 }
@@ -0,0 +1,196 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use uuid::Uuid;
+
+/// Tells a running task it should stop, either because someone asked with
+/// [`CancellationToken::cancel`] or because its deadline has passed. Tasks
+/// can't be stopped from outside, so long-running ones should check
+/// [`CancellationToken::is_cancelled`] between steps and bail out. Clones
+/// share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    timeout: Option<Duration>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that cancels itself once `timeout` has passed from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            deadline: Instant::now().checked_add(timeout),
+            timeout: Some(timeout),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.was_cancelled() || self.is_expired()
+    }
+
+    /// Whether [`CancellationToken::cancel`] was called, as opposed to the
+    /// deadline passing.
+    pub fn was_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// How long is left before the deadline, if there is one.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+pub(crate) type Logger = Arc<dyn Fn(&TaskContext, &str) + Send + Sync>;
+
+/// What a worker tells a task about the attempt it's running: which task and
+/// attempt this is, its priority, which worker is running it, a token to
+/// check for cancellation, and hooks to report progress, log and reach the
+/// worker's shared state.
+#[derive(Clone)]
+pub struct TaskContext {
+    pub(crate) token: CancellationToken,
+    pub(crate) task_id: Uuid,
+    pub(crate) attempt: u32,
+    pub(crate) priority: Option<i64>,
+    pub(crate) enqueued_at: SystemTime,
+    pub(crate) worker_id: Uuid,
+    pub(crate) channel: String,
+    pub(crate) progress: Arc<AtomicU64>,
+    pub(crate) logger: Option<Logger>,
+    pub(crate) state: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for TaskContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskContext")
+            .field("task_id", &self.task_id)
+            .field("attempt", &self.attempt)
+            .field("priority", &self.priority)
+            .field("worker_id", &self.worker_id)
+            .field("channel", &self.channel)
+            .field("progress", &self.progress())
+            .finish()
+    }
+}
+
+impl TaskContext {
+    /// A context outside any worker, for running a task by hand or in a
+    /// test: a fresh task id, a first attempt, no priority and no worker.
+    pub fn new(token: CancellationToken) -> Self {
+        Self {
+            token,
+            task_id: Uuid::new_v4(),
+            attempt: 1,
+            priority: None,
+            enqueued_at: SystemTime::now(),
+            worker_id: Uuid::nil(),
+            channel: String::new(),
+            progress: Arc::default(),
+            logger: None,
+            state: None,
+        }
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Whether the task should stop; see [`CancellationToken::is_cancelled`].
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.token.deadline()
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.token.remaining()
+    }
+
+    /// The id the task keeps across retries, and in its dead letter.
+    pub fn task_id(&self) -> Uuid {
+        self.task_id
+    }
+
+    /// Which attempt this is, starting from one.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// The task's priority, if it has one; see
+    /// [`Task::priority`](crate::task::Task::priority). A [`Parcel`]
+    /// reports its envelope's.
+    ///
+    /// [`Parcel`]: crate::registry::Parcel
+    pub fn priority(&self) -> Option<i64> {
+        self.priority
+    }
+
+    pub fn enqueued_at(&self) -> SystemTime {
+        self.enqueued_at
+    }
+
+    pub fn worker_id(&self) -> Uuid {
+        self.worker_id
+    }
+
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Records how far along the attempt is, from 0.0 to 1.0, for
+    /// [`Worker::progress`](crate::worker::Worker::progress) to report.
+    pub fn set_progress(&self, fraction: f64) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        self.progress.store(fraction.to_bits(), Ordering::SeqCst);
+    }
+
+    pub fn progress(&self) -> f64 {
+        f64::from_bits(self.progress.load(Ordering::SeqCst))
+    }
+
+    /// Logs `message` tagged with the task's id through the worker's logger,
+    /// or to stderr if it hasn't got one.
+    pub fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger(self, message),
+            None => eprintln!(
+                "[{}] task {} (attempt {}): {message}",
+                self.channel, self.task_id, self.attempt
+            ),
+        }
+    }
+
+    /// The state shared by every task on the worker, if it was set with
+    /// [`Worker::set_state`](crate::worker::Worker::set_state) and is an `S`.
+    pub fn state<S: Any>(&self) -> Option<&S> {
+        self.state.as_deref()?.downcast_ref()
+    }
+}
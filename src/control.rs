@@ -0,0 +1,49 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// What can be asked of the workers on a channel from another process,
+/// through [`Client::control`](crate::client::Client::control). Sent as the
+/// bare command name, so `PUBLISH toretsu:emails:control pause` from
+/// `redis-cli` works too.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Command {
+    /// Stop starting tasks, keeping them queued; see
+    /// [`Worker::pause`](crate::worker::Worker::pause).
+    Pause,
+    /// Start tasks again.
+    Resume,
+}
+
+impl Command {
+    /// The Pub/Sub channel commands for workers on `channel` go out on.
+    pub fn generate_key(channel: &str) -> String {
+        format!("toretsu:{channel}:control")
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pause => "pause",
+            Self::Resume => "resume",
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(command: &str) -> Result<Self> {
+        match command.trim() {
+            "pause" => Ok(Self::Pause),
+            "resume" => Ok(Self::Resume),
+            _ => Err(Error::Control(format!("unknown command `{command}`"))),
+        }
+    }
+}
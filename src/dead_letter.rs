@@ -0,0 +1,114 @@
+use std::time::SystemTime;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entry::Entry;
+
+/// A task that failed on its last allowed attempt, or panicked with no
+/// retries left, kept so it can be inspected and replayed.
+#[derive(Debug)]
+pub struct DeadLetter<T> {
+    pub id: Uuid,
+    pub task: T,
+    pub error: String,
+    pub attempts: u32,
+    pub enqueued_at: SystemTime,
+    pub failed_at: SystemTime,
+    pub(crate) raw: Option<String>,
+    pub(crate) record: Option<String>,
+}
+
+// How a dead letter is stored in Redis. The task is kept as the exact JSON
+// it was queued as.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    id: Uuid,
+    error: String,
+    attempts: u32,
+    enqueued_at: SystemTime,
+    failed_at: SystemTime,
+    task: serde_json::Value,
+}
+
+impl<T> DeadLetter<T> {
+    pub fn new(entry: Entry<T>, error: String) -> Self {
+        Self {
+            id: entry.id,
+            error,
+            attempts: entry.attempts,
+            enqueued_at: entry.enqueued_at,
+            failed_at: SystemTime::now(),
+            raw: entry.raw,
+            record: None,
+            task: entry.task,
+        }
+    }
+
+    /// Turns this back into a fresh entry, keeping its id so the replay can
+    /// be traced back to the original.
+    pub fn into_entry(self) -> Entry<T> {
+        let mut entry = Entry::new(self.task);
+        entry.id = self.id;
+        entry.raw = self.raw;
+        entry
+    }
+
+    pub(crate) fn encode_record(&self, raw: &str) -> serde_json::Result<String> {
+        let record = Record {
+            id: self.id,
+            error: self.error.clone(),
+            attempts: self.attempts,
+            enqueued_at: self.enqueued_at,
+            failed_at: self.failed_at,
+            task: serde_json::from_str(raw)?,
+        };
+
+        serde_json::to_string(&record)
+    }
+
+    /// A record for something in a backup that couldn't be decoded as a
+    /// task, kept in the dead-letter list with why, so it can be looked into
+    /// rather than block the rest from loading.
+    pub(crate) fn undecodable_record(raw: &str, error: &serde_json::Error) -> String {
+        let now = SystemTime::now();
+        let record = Record {
+            id: Uuid::new_v4(),
+            error: format!("undecodable task: {error}"),
+            attempts: 0,
+            enqueued_at: now,
+            failed_at: now,
+            task: serde_json::from_str(raw)
+                .unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+        };
+
+        serde_json::to_string(&record).unwrap_or_default()
+    }
+
+    pub(crate) fn decode_record(record: String) -> serde_json::Result<Self>
+    where
+        T: DeserializeOwned,
+    {
+        let Record {
+            id,
+            error,
+            attempts,
+            enqueued_at,
+            failed_at,
+            task,
+        } = serde_json::from_str(&record)?;
+        let raw = task.to_string();
+
+        Ok(Self {
+            id,
+            task: serde_json::from_value(task)?,
+            error,
+            attempts,
+            enqueued_at,
+            failed_at,
+            raw: Some(raw),
+            record: Some(record),
+        })
+    }
+}
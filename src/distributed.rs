@@ -0,0 +1,247 @@
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::client::Client;
+use crate::envelope::Envelope;
+use crate::error::{Error, Result};
+use crate::listener::Listener;
+
+// How long a pull waits on an empty queue before checking whether it's been
+// asked to stop.
+const POLL: Duration = Duration::from_millis(250);
+
+// Members are stored as `{rank}:{task}`, where the rank counts down as tasks
+// are pushed. Redis pops the greatest member among equal scores, so equal
+// priorities come out in the order they went in.
+const RANK_MAX: u64 = 9_999_999_999_999_999;
+
+/// The number a [`DistributedQueue`] orders a task by - higher comes first.
+/// Priorities beyond ±2^53 lose precision as sorted-set scores.
+pub trait Priority {
+    fn priority(&self) -> i64;
+}
+
+impl Priority for Envelope {
+    fn priority(&self) -> i64 {
+        self.priority
+    }
+}
+
+/// A priority queue kept in a Redis sorted set, so any number of processes
+/// can share one global order. Pops are atomic: each task goes to exactly
+/// one caller. Mirrors the shape of [`Queue`](crate::queue::Queue), with
+/// every method returning a `Result`. A task that's popped but can't be
+/// decoded is moved, exactly as it was stored, to the list at `dead_key`.
+pub struct DistributedQueue<T> {
+    pub name: String,
+    pub key: String,
+    pub rank_key: String,
+    pub dead_key: String,
+    client: Client,
+    task: PhantomData<fn() -> T>,
+}
+
+impl<T> DistributedQueue<T> {
+    pub fn generate_key(name: &str) -> String {
+        format!("toretsu:{name}:zqueue")
+    }
+
+    pub fn generate_rank_key(name: &str) -> String {
+        format!("toretsu:{name}:zqueue:rank")
+    }
+
+    pub fn generate_dead_key(name: &str) -> String {
+        format!("toretsu:{name}:zqueue:dead")
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl<T> DistributedQueue<T>
+where
+    T: Priority + Serialize + DeserializeOwned,
+{
+    pub fn init(name: &str, client: Client) -> Self {
+        Self {
+            name: name.to_string(),
+            key: Self::generate_key(name),
+            rank_key: Self::generate_rank_key(name),
+            dead_key: Self::generate_dead_key(name),
+            client,
+            task: PhantomData,
+        }
+    }
+
+    /// Connects a fresh client; see [`Client::try_new`].
+    pub fn new(name: &str) -> Result<Self> {
+        Ok(Self::init(name, Client::try_new()?))
+    }
+
+    fn decode(member: &str) -> Result<T> {
+        let raw = match member.split_once(':') {
+            Some((_, raw)) => raw,
+            None => member,
+        };
+
+        Ok(serde_json::from_str(raw)?)
+    }
+
+    // Decodes a member that's already been removed from the queue, setting
+    // it aside if it can't be, so it isn't lost.
+    fn take(&mut self, member: &str) -> Result<T> {
+        let err = match Self::decode(member) {
+            Err(err @ Error::Serialization(_)) => err,
+            decoded => return decoded,
+        };
+        let _: () = self.client.connection.rpush(&self.dead_key, member)?;
+
+        Err(err)
+    }
+
+    pub fn len(&mut self) -> Result<usize> {
+        Ok(self.client.connection.zcard(&self.key)?)
+    }
+
+    pub fn is_empty(&mut self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        Ok(self
+            .client
+            .connection
+            .del(&[&self.key, &self.rank_key, &self.dead_key])?)
+    }
+
+    pub fn push(&mut self, value: &T) -> Result<()> {
+        self.extend([value])
+    }
+
+    pub fn extend<'a, I>(&mut self, iter: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let tasks = iter
+            .into_iter()
+            .map(|task| serde_json::to_string(task).map(|raw| (task.priority(), raw)))
+            .collect::<serde_json::Result<Vec<(i64, String)>>>()?;
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let last: u64 = self.client.connection.incr(&self.rank_key, tasks.len())?;
+        let first = last - tasks.len() as u64 + 1;
+        let members: Vec<(f64, String)> = tasks
+            .into_iter()
+            .zip(first..)
+            .map(|((priority, raw), sequence)| {
+                let rank = RANK_MAX - sequence;
+                (priority as f64, format!("{rank:016}:{raw}"))
+            })
+            .collect();
+
+        Ok(self.client.connection.zadd_multiple(&self.key, &members)?)
+    }
+
+    pub fn pop(&mut self) -> Result<Option<T>> {
+        let popped: Vec<(String, f64)> = self.client.connection.zpopmax(&self.key, 1)?;
+        popped
+            .first()
+            .map(|(member, _)| self.take(member))
+            .transpose()
+    }
+
+    /// Waits up to `timeout` for a task if the queue is empty.
+    pub fn pop_timeout(&mut self, timeout: Duration) -> Result<Option<T>> {
+        let popped: Option<(String, String, f64)> = redis::cmd("BZPOPMAX")
+            .arg(&self.key)
+            .arg(timeout.as_secs_f64())
+            .query(&mut self.client.connection)?;
+
+        popped.map(|(_, member, _)| self.take(&member)).transpose()
+    }
+
+    pub fn peek(&mut self) -> Result<Option<T>> {
+        let top: Vec<String> = self.client.connection.zrevrange(&self.key, 0, 0)?;
+        top.first().map(|member| Self::decode(member)).transpose()
+    }
+
+    /// Removes every task, highest priority first. Any that can't be decoded
+    /// are set aside rather than fail the whole drain.
+    pub fn drain_sorted(&mut self) -> Result<Vec<T>> {
+        let (members,): (Vec<String>,) = redis::pipe()
+            .atomic()
+            .zrevrange(&self.key, 0, -1)
+            .del(&self.key)
+            .ignore()
+            .query(&mut self.client.connection)?;
+
+        let mut tasks = Vec::with_capacity(members.len());
+        for member in members {
+            match self.take(&member) {
+                Ok(task) => tasks.push(task),
+                Err(Error::Serialization(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    /// Every task, highest priority first, without removing any.
+    pub fn to_sorted_vec(&mut self) -> Result<Vec<T>> {
+        let members: Vec<String> = self.client.connection.zrevrange(&self.key, 0, -1)?;
+        members.iter().map(|member| Self::decode(member)).collect()
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl<T> DistributedQueue<T>
+where
+    T: Priority + Serialize + DeserializeOwned + Send + 'static,
+{
+    // Pops tasks into `sink` whenever it has room, until it breaks or the
+    // listener is stopped. A lost connection is re-opened like a
+    // subscription's.
+    fn run<F>(mut self, stop: &AtomicBool, sink: F) -> Result<()>
+    where
+        F: FnMut(Option<T>) -> ControlFlow<(), usize>,
+    {
+        let fetch = |_| {
+            let err = match self.pop_timeout(POLL) {
+                Ok(task) => return Ok(task),
+                // Already popped and set aside, as no amount of retrying will
+                // decode it.
+                Err(Error::Serialization(_)) => return Ok(None),
+                Err(err) => err,
+            };
+
+            let redis = self.client.redis.clone();
+            if let Some(connection) =
+                Listener::reconnect(stop, err, || Ok(redis.get_connection()?))?
+            {
+                self.client.connection = connection;
+            }
+
+            Ok(None)
+        };
+
+        Listener::fill(stop, fetch, sink)
+    }
+
+    /// Pops tasks on a background thread and hands each one to `sink`,
+    /// which returns how many more it has room for. Used by
+    /// [`Worker::pull_with`](crate::worker::Worker::pull_with).
+    pub(crate) fn pull<F>(self, sink: F) -> Listener
+    where
+        F: FnMut(Option<T>) -> ControlFlow<(), usize> + Send + 'static,
+    {
+        Listener::start("toretsu-puller", move |stop| self.run(stop, sink))
+    }
+}
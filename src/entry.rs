@@ -0,0 +1,141 @@
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::time::SystemTime;
+
+use uuid::Uuid;
+
+use crate::error::Result;
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Acknowledges a task taken from a stream or broker once the worker is done
+/// with it, whether it finished or was dead-lettered.
+pub(crate) struct Receipt(Box<dyn Fn() -> Result<()> + Send + Sync>);
+
+impl Receipt {
+    pub fn new<F>(ack: F) -> Self
+    where
+        F: Fn() -> Result<()> + Send + Sync + 'static,
+    {
+        Self(Box::new(ack))
+    }
+
+    pub fn ack(&self) -> Result<()> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for Receipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Receipt")
+    }
+}
+
+/// A task's output, type-erased, or the reason it failed.
+pub(crate) type Outcome = std::result::Result<Box<dyn Any + Send>, String>;
+
+/// Hands a task's output, or why it failed, to the
+/// [`Handle`](crate::output::Handle) waiting on it. Dropped unsent, it tells
+/// the handle the task is gone.
+pub(crate) struct Reply(Option<Box<dyn FnOnce(Outcome) + Send + Sync>>);
+
+impl Reply {
+    pub fn new<F>(send: F) -> Self
+    where
+        F: FnOnce(Outcome) + Send + Sync + 'static,
+    {
+        Self(Some(Box::new(send)))
+    }
+
+    /// A reply for output of type `O`, which is all a worker ever sends it.
+    pub fn typed<O, F>(send: F) -> Self
+    where
+        O: 'static,
+        F: FnOnce(std::result::Result<O, String>) + Send + Sync + 'static,
+    {
+        Self::new(move |outcome: Outcome| {
+            send(outcome.and_then(|output| match output.downcast::<O>() {
+                Ok(output) => Ok(*output),
+                Err(_) => Err(String::from("task produced the wrong output")),
+            }))
+        })
+    }
+
+    pub fn send(mut self, outcome: Outcome) {
+        if let Some(send) = self.0.take() {
+            send(outcome);
+        }
+    }
+}
+
+impl Drop for Reply {
+    fn drop(&mut self) {
+        if let Some(send) = self.0.take() {
+            send(Err(String::from("task was dropped before it finished")));
+        }
+    }
+}
+
+impl fmt::Debug for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Reply")
+    }
+}
+
+/// A task waiting in a worker's queue, along with what the worker knows
+/// about it. Entries are ordered by their task, and tasks that compare equal
+/// are served in the order they were assigned.
+#[derive(Debug)]
+pub struct Entry<T> {
+    pub id: Uuid,
+    pub task: T,
+    pub attempts: u32,
+    pub enqueued_at: SystemTime,
+    pub(crate) raw: Option<String>,
+    pub(crate) receipt: Option<Receipt>,
+    pub(crate) reply: Option<Reply>,
+    sequence: u64,
+}
+
+impl<T> Entry<T> {
+    pub fn new(task: T) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            task,
+            attempts: 0,
+            enqueued_at: SystemTime::now(),
+            raw: None,
+            receipt: None,
+            reply: None,
+            sequence: SEQUENCE.fetch_add(1, AtomicOrdering::Relaxed),
+        }
+    }
+
+    pub fn into_task(self) -> T {
+        self.task
+    }
+}
+
+impl<T: Ord> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Entry<T> {}
+
+impl<T: Ord> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.task
+            .cmp(&other.task)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
@@ -0,0 +1,62 @@
+use redis::{ErrorKind, FromRedisValue, RedisResult, RedisWrite, ToRedisArgs, Value};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The wire format for a task travelling between processes: the name the
+/// task type was registered under, its priority and its serialized body.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub id: Uuid,
+    pub kind: String,
+    pub priority: i64,
+    pub payload: serde_json::Value,
+}
+
+impl Envelope {
+    pub fn init(id: Uuid, kind: &str, priority: i64, payload: serde_json::Value) -> Self {
+        Self {
+            id,
+            kind: kind.to_string(),
+            priority,
+            payload,
+        }
+    }
+
+    pub fn new<T: Serialize>(kind: &str, priority: i64, task: &T) -> serde_json::Result<Self> {
+        let payload = serde_json::to_value(task)?;
+
+        Ok(Self::init(Uuid::new_v4(), kind, priority, payload))
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(raw: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(raw)
+    }
+
+    pub fn from_slice(raw: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(raw)
+    }
+}
+
+impl ToRedisArgs for Envelope {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        // Every field is already JSON-safe, so this can't fail.
+        let raw = serde_json::to_vec(self).unwrap_or_default();
+        out.write_arg(&raw)
+    }
+}
+
+impl FromRedisValue for Envelope {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        match v {
+            Value::Data(raw) => Ok(Self::from_slice(raw)?),
+            _ => Err((ErrorKind::TypeError, "Envelope must be a JSON string").into()),
+        }
+    }
+}
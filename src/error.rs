@@ -0,0 +1,111 @@
+use std::fmt;
+
+use rayon::ThreadPoolBuildError;
+use redis::RedisError;
+
+#[derive(Debug)]
+pub enum Error {
+    /// No usable configuration could be loaded from the environment or files.
+    Config(confique::Error),
+    /// Redis couldn't be reached, or the connection dropped or timed out.
+    Connection(RedisError),
+    /// Redis answered, but with an error or a reply we didn't expect.
+    Protocol(RedisError),
+    /// A task or envelope couldn't be encoded or decoded.
+    Serialization(serde_json::Error),
+    /// A worker's thread pool couldn't be started.
+    Pool(ThreadPoolBuildError),
+    /// A file-backed broker couldn't read or write its directory.
+    Io(std::io::Error),
+    /// A schedule, such as a cron expression, couldn't be understood.
+    Schedule(String),
+    /// A task ran out of attempts, or was dropped before it could finish.
+    Task(String),
+    /// A workflow was put together wrong, such as with a step from another.
+    Workflow(String),
+    /// The worker has been shut down and takes no more tasks.
+    Closed,
+    /// A control message wasn't a command a worker understands.
+    Control(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn is_connection(&self) -> bool {
+        matches!(self, Self::Connection(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(err) => write!(f, "invalid configuration: {err}"),
+            Self::Connection(err) => write!(f, "redis connection failed: {err}"),
+            Self::Protocol(err) => write!(f, "redis error: {err}"),
+            Self::Serialization(err) => write!(f, "serialization failed: {err}"),
+            Self::Pool(err) => write!(f, "failed to start worker threads: {err}"),
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            Self::Schedule(err) => write!(f, "invalid schedule: {err}"),
+            Self::Task(err) => write!(f, "task failed: {err}"),
+            Self::Workflow(err) => write!(f, "invalid workflow: {err}"),
+            Self::Closed => write!(f, "worker has shut down"),
+            Self::Control(err) => write!(f, "invalid control message: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Config(err) => Some(err),
+            Self::Connection(err) | Self::Protocol(err) => Some(err),
+            Self::Serialization(err) => Some(err),
+            Self::Pool(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Schedule(_)
+            | Self::Task(_)
+            | Self::Workflow(_)
+            | Self::Closed
+            | Self::Control(_) => None,
+        }
+    }
+}
+
+impl From<confique::Error> for Error {
+    fn from(err: confique::Error) -> Self {
+        Self::Config(err)
+    }
+}
+
+impl From<RedisError> for Error {
+    fn from(err: RedisError) -> Self {
+        let lost = err.is_io_error()
+            || err.is_connection_refusal()
+            || err.is_connection_dropped()
+            || err.is_timeout();
+
+        match lost {
+            true => Self::Connection(err),
+            false => Self::Protocol(err),
+        }
+    }
+}
+
+impl From<ThreadPoolBuildError> for Error {
+    fn from(err: ThreadPoolBuildError) -> Self {
+        Self::Pool(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Serialization(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
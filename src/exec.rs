@@ -0,0 +1,72 @@
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::context::{CancellationToken, TaskContext};
+use crate::task::{Failure, Task};
+
+// How often a running program is checked on for having exited or been
+// cancelled.
+const POLL: Duration = Duration::from_millis(50);
+
+/// Runs a program, so the stock `toretsu` binary can do work without a host
+/// crate. Sent as the kind [`Exec::KIND`] with a payload such as
+/// `{"program": "./backup.sh", "args": ["--full"]}`.
+///
+/// It fails if the program can't be started or exits unsuccessfully, and the
+/// program is killed if the task is cancelled or runs out of time. Anyone
+/// who can publish to a worker's channel can run anything the worker can, so
+/// only enable the `exec` feature where the channel is trusted.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Exec {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Exec {
+    pub const KIND: &'static str = "exec";
+
+    pub fn new<I, S>(program: &str, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            program: program.to_string(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Task for Exec {
+    fn process(&mut self) {
+        let _ = self.try_process();
+    }
+
+    fn try_process(&mut self) -> Result<(), Failure> {
+        self.try_process_with(&TaskContext::new(CancellationToken::new()))
+    }
+
+    fn try_process_with(&mut self, ctx: &TaskContext) -> Result<(), Failure> {
+        let mut child = Command::new(&self.program).args(&self.args).spawn()?;
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return match status.success() {
+                    true => Ok(()),
+                    false => Err(format!("`{}` exited with {status}", self.program).into()),
+                };
+            }
+
+            if ctx.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("`{}` was stopped before it exited", self.program).into());
+            }
+
+            thread::sleep(POLL);
+        }
+    }
+}
@@ -1,5 +1,29 @@
+#[cfg(feature = "tokio")]
+pub mod async_client;
+pub mod backup;
+pub mod broker;
+pub mod cli;
 pub mod client;
 pub mod config;
+pub mod context;
+pub mod control;
+pub mod dead_letter;
+pub mod distributed;
+pub mod entry;
+pub mod envelope;
+pub mod error;
+#[cfg(feature = "exec")]
+pub mod exec;
+pub mod listener;
+pub mod output;
 pub mod queue;
+pub mod registry;
+pub mod results;
+pub mod retry;
+pub mod router;
+pub mod scheduler;
+pub mod shutdown;
+pub mod streams;
 pub mod task;
 pub mod worker;
+pub mod workflow;
@@ -0,0 +1,218 @@
+use std::ops;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use redis::{Client as Redis, Connection, ControlFlow, Msg};
+
+use crate::error::{Error, Result};
+use crate::retry::RetryPolicy;
+
+// What a listener is subscribed to, kept so it can resubscribe after
+// reconnecting.
+pub(crate) struct Topics {
+    pub channels: Vec<Vec<u8>>,
+    pub patterns: Vec<Vec<u8>>,
+}
+
+// How long a read blocks before the listener checks whether it's been
+// asked to stop.
+const POLL: Duration = Duration::from_millis(250);
+
+/// A running subscription, started by [`Client::listen`](crate::client::Client::listen)
+/// or one of its relatives. Dropping the handle leaves the subscription
+/// running in the background.
+pub struct Listener {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Result<()>>,
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Listener {
+    /// Runs `body` on a thread named `name` until it returns, handing it the
+    /// flag [`Listener::stop`] raises.
+    pub(crate) fn start<F>(name: &str, body: F) -> Self
+    where
+        F: FnOnce(&AtomicBool) -> Result<()> + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || body(&stopped))
+            .expect("Failed to Start Listener Thread");
+
+        Self { stop, thread }
+    }
+
+    pub(crate) fn spawn<F, U>(redis: Redis, topics: Topics, func: F) -> Result<Self>
+    where
+        F: FnMut(Msg) -> ControlFlow<U> + Send + 'static,
+    {
+        let connection = Self::subscribe(&redis, &topics)?;
+        let listener = Self::start("toretsu-listener", move |stop| {
+            Self::run(redis, topics, connection, stop, func)
+        });
+
+        Ok(listener)
+    }
+
+    fn subscribe(redis: &Redis, topics: &Topics) -> Result<Connection> {
+        let mut connection = redis.get_connection()?;
+        connection.set_read_timeout(Some(POLL))?;
+
+        let mut pubsub = connection.as_pubsub();
+        if !topics.channels.is_empty() {
+            pubsub.subscribe(&topics.channels)?;
+        }
+        if !topics.patterns.is_empty() {
+            pubsub.psubscribe(&topics.patterns)?;
+        }
+        drop(pubsub);
+
+        Ok(connection)
+    }
+
+    // Hands messages to `func` until it breaks, the listener is stopped or
+    // the connection is lost for good. Messages published while the
+    // connection was down are lost.
+    fn run<F, U>(
+        redis: Redis,
+        topics: Topics,
+        mut connection: Connection,
+        stop: &AtomicBool,
+        mut func: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Msg) -> ControlFlow<U>,
+    {
+        while !stop.load(Ordering::SeqCst) {
+            let err = match connection.recv_response() {
+                Ok(value) => match Msg::from_value(&value).map(&mut func) {
+                    Some(ControlFlow::Break(_)) => break,
+                    _ => continue,
+                },
+                Err(err) if err.is_timeout() => continue,
+                Err(err) => Error::from(err),
+            };
+
+            connection = match Self::reconnect(stop, err, || Self::subscribe(&redis, &topics))? {
+                Some(connection) => connection,
+                None => break,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Re-opens a connection lost to `err` with `connect`, backing off
+    /// between attempts for about half a minute. Returns `None` if the
+    /// listener is stopped meanwhile, and the error if it isn't a lost
+    /// connection or Redis doesn't come back.
+    pub(crate) fn reconnect<C>(
+        stop: &AtomicBool,
+        err: Error,
+        mut connect: C,
+    ) -> Result<Option<Connection>>
+    where
+        C: FnMut() -> Result<Connection>,
+    {
+        if !err.is_connection() {
+            return Err(err);
+        }
+
+        let policy = Self::reconnect_policy();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            if !Self::pause(stop, policy.backoff(attempts)) {
+                return Ok(None);
+            }
+
+            match connect() {
+                Ok(connection) => return Ok(Some(connection)),
+                Err(err) if err.is_connection() && policy.should_retry(attempts) => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// How lost connections are re-opened: ten attempts, about half a minute
+    /// in all.
+    pub(crate) fn reconnect_policy() -> RetryPolicy {
+        RetryPolicy::new(10).with_max_delay(Duration::from_secs(10))
+    }
+
+    /// Hands `sink` what `fetch` brings in, asking for no more than `sink`
+    /// last said it had room for, until it breaks or the listener is
+    /// stopped. While it's full, `sink` is handed an empty batch now and
+    /// then to ask again.
+    pub(crate) fn fill<T, F, S>(stop: &AtomicBool, mut fetch: F, mut sink: S) -> Result<()>
+    where
+        T: Default,
+        F: FnMut(usize) -> Result<T>,
+        S: FnMut(T) -> ops::ControlFlow<(), usize>,
+    {
+        let mut room = match sink(T::default()) {
+            ops::ControlFlow::Continue(room) => room,
+            ops::ControlFlow::Break(()) => return Ok(()),
+        };
+
+        while !stop.load(Ordering::SeqCst) {
+            let fetched = match room {
+                0 => {
+                    thread::sleep(POLL);
+                    T::default()
+                }
+                room => fetch(room)?,
+            };
+            room = match sink(fetched) {
+                ops::ControlFlow::Continue(room) => room,
+                ops::ControlFlow::Break(()) => break,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Sleeps for `delay`, waking early if the listener is stopped. Returns
+    /// whether it should carry on.
+    pub(crate) fn pause(stop: &AtomicBool, delay: Duration) -> bool {
+        let until = Instant::now() + delay;
+        while !stop.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now >= until {
+                return true;
+            }
+            thread::sleep((until - now).min(POLL));
+        }
+
+        false
+    }
+
+    /// Asks the subscription to end. It finishes within a fraction of a
+    /// second; use [`Listener::join`] to wait for it.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the subscription to end, returning the error that ended it
+    /// if it didn't stop cleanly. A panic in the callback is propagated.
+    pub fn join(self) -> Result<()> {
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    pub fn shutdown(self) -> Result<()> {
+        self.stop();
+        self.join()
+    }
+}
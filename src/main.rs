@@ -0,0 +1,9 @@
+use std::process::ExitCode;
+
+use toretsu::cli;
+use toretsu::registry::Registry;
+
+#[cfg(not(tarpaulin_include))]
+fn main() -> ExitCode {
+    cli::run(Registry::builtin())
+}
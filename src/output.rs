@@ -0,0 +1,127 @@
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::entry::Reply;
+use crate::error::{Error, Result};
+
+/// A task that hands back a value once it's done. The worker takes it out
+/// with [`Produce::output`] after a successful attempt and passes it to the
+/// task's [`Handle`], and to the worker's result backend if it has one.
+pub trait Produce {
+    type Output: Send + 'static;
+
+    /// Takes what the task produced out of it. It's only asked for when
+    /// something is waiting on the output: a [`Handle`], a workflow step
+    /// after it, or a result backend. `None` then fails the attempt just as
+    /// an `Err` from the task would, so it's retried per the retry policy
+    /// and dead-lettered once it runs out of attempts.
+    fn output(&mut self) -> Option<Self::Output>;
+
+    /// The id to store the task's result under. Tasks sent from another
+    /// process carry one, so the sender knows where to look it up; the rest
+    /// are stored under the id their worker gives them.
+    fn result_id(&self) -> Option<Uuid> {
+        None
+    }
+
+    /// Hands a [`Workflow`](crate::workflow::Workflow) step the outputs of
+    /// the steps it runs after, in the order they were given, just before
+    /// it's queued.
+    fn receive(&mut self, _upstream: Vec<Self::Output>) {}
+}
+
+struct Slot<O> {
+    outcome: Mutex<Option<Result<O>>>,
+    ready: Condvar,
+}
+
+/// Waits on the output of a task queued with
+/// [`Worker::submit`](crate::worker::Worker::submit). A task that fails for
+/// good, or is dropped with its worker before it runs, comes back as an
+/// [`Error::Task`].
+pub struct Handle<O> {
+    id: Uuid,
+    slot: Arc<Slot<O>>,
+}
+
+impl<O> fmt::Debug for Handle<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("id", &self.id)
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+impl<O: Send + 'static> Handle<O> {
+    pub(crate) fn new(id: Uuid) -> (Self, Reply) {
+        let slot = Arc::new(Slot {
+            outcome: Mutex::new(None),
+            ready: Condvar::new(),
+        });
+
+        let sender = Arc::clone(&slot);
+        let reply = Reply::typed(move |outcome: std::result::Result<O, String>| {
+            *sender.outcome.lock().unwrap() = Some(outcome.map_err(Error::Task));
+            sender.ready.notify_all();
+        });
+
+        (Self { id, slot }, reply)
+    }
+}
+
+impl<O> Handle<O> {
+    /// The id the task's result is stored under: its
+    /// [`Produce::result_id`] if it has one, and the id its worker gave it,
+    /// which its dead letter goes by too, otherwise.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.slot.outcome.lock().unwrap().is_some()
+    }
+
+    /// Blocks until the task is done, then returns its output.
+    pub fn wait(self) -> Result<O> {
+        let mut outcome = self.slot.outcome.lock().unwrap();
+        loop {
+            match outcome.take() {
+                Some(outcome) => return outcome,
+                None => outcome = self.slot.ready.wait(outcome).unwrap(),
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for the task, handing the handle back if it
+    /// isn't done by then.
+    pub fn wait_timeout(self, timeout: Duration) -> std::result::Result<Result<O>, Self> {
+        let until = Instant::now() + timeout;
+        let mut outcome = self.slot.outcome.lock().unwrap();
+        loop {
+            if let Some(outcome) = outcome.take() {
+                return Ok(outcome);
+            }
+
+            let now = Instant::now();
+            if now >= until {
+                drop(outcome);
+                return Err(self);
+            }
+            outcome = self
+                .slot
+                .ready
+                .wait_timeout(outcome, until - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Returns the task's output if it's done, and the handle otherwise.
+    pub fn try_wait(self) -> std::result::Result<Result<O>, Self> {
+        self.wait_timeout(Duration::ZERO)
+    }
+}
@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use redis::Msg;
+use serde::de::{DeserializeOwned, Error};
+use serde::{Serialize, Serializer};
+
+use crate::context::TaskContext;
+use crate::envelope::Envelope;
+use crate::retry::RetryPolicy;
+use crate::task::{Failure, Task};
+
+type Decoder = fn(serde_json::Value) -> serde_json::Result<Box<dyn Task + Send>>;
+
+fn decode<T>(payload: serde_json::Value) -> serde_json::Result<Box<dyn Task + Send>>
+where
+    T: Task + DeserializeOwned + Send + 'static,
+{
+    let task: T = serde_json::from_value(payload)?;
+    Ok(Box::new(task))
+}
+
+/// Maps the `kind` of an [`Envelope`] to the task type it should be
+/// rebuilt as, so a worker can run tasks published by another process.
+#[derive(Clone, Default)]
+pub struct Registry {
+    decoders: HashMap<String, Decoder>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The task kinds that ship with the crate, which the stock `toretsu`
+    /// binary runs: [`Exec`](crate::exec::Exec) with the `exec` feature, and
+    /// none without it.
+    pub fn builtin() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+        #[cfg(feature = "exec")]
+        registry.register::<crate::exec::Exec>(crate::exec::Exec::KIND);
+
+        registry
+    }
+
+    pub fn register<T>(&mut self, kind: &str) -> &mut Self
+    where
+        T: Task + DeserializeOwned + Send + 'static,
+    {
+        self.decoders.insert(kind.to_string(), decode::<T>);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+
+    pub fn contains(&self, kind: &str) -> bool {
+        self.decoders.contains_key(kind)
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.decoders.keys().map(String::as_str)
+    }
+
+    pub fn decode(&self, envelope: Envelope) -> serde_json::Result<Parcel> {
+        let decoder = match self.decoders.get(&envelope.kind) {
+            Some(decoder) => decoder,
+            None => {
+                let msg = format!("unregistered task kind `{}`", envelope.kind);
+                return Err(serde_json::Error::custom(msg));
+            }
+        };
+
+        let task = decoder(envelope.payload.clone())?;
+        Ok(Parcel { envelope, task })
+    }
+
+    pub fn decode_json(&self, raw: &str) -> serde_json::Result<Parcel> {
+        let envelope = Envelope::from_json(raw)?;
+        self.decode(envelope)
+    }
+
+    pub fn decode_slice(&self, raw: &[u8]) -> serde_json::Result<Parcel> {
+        let envelope = Envelope::from_slice(raw)?;
+        self.decode(envelope)
+    }
+
+    pub fn decode_msg(&self, msg: &Msg) -> serde_json::Result<Parcel> {
+        self.decode_slice(msg.get_payload_bytes())
+    }
+}
+
+/// A task rebuilt from an [`Envelope`]. It is ordered by the envelope's
+/// priority alone, so it can sit in a `Queue` alongside other registered
+/// kinds, and parcels of equal priority are run in the order they arrived.
+pub struct Parcel {
+    pub envelope: Envelope,
+    task: Box<dyn Task + Send>,
+}
+
+impl Task for Parcel {
+    fn process(&mut self) {
+        self.task.process()
+    }
+
+    fn try_process(&mut self) -> Result<(), Failure> {
+        self.task.try_process()
+    }
+
+    fn try_process_with(&mut self, ctx: &TaskContext) -> Result<(), Failure> {
+        self.task.try_process_with(ctx)
+    }
+
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.task.retry_policy()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.task.timeout()
+    }
+
+    fn priority(&self) -> Option<i64> {
+        Some(self.envelope.priority)
+    }
+}
+
+impl Serialize for Parcel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.envelope.serialize(serializer)
+    }
+}
+
+impl PartialEq for Parcel {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Parcel {}
+
+impl PartialOrd for Parcel {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Parcel {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.envelope.priority.cmp(&other.envelope.priority)
+    }
+}
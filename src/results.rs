@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+
+// How often `ResultBackend::wait` looks for a result.
+const POLL: Duration = Duration::from_millis(50);
+
+/// Keeps what tasks produced in Redis for a while after they finish, keyed
+/// by task id, so another process can look them up. Failures are kept too,
+/// and come back as [`Error::Task`].
+#[derive(Clone)]
+pub struct ResultBackend {
+    ttl: Duration,
+    client: Arc<Mutex<Client>>,
+}
+
+impl ResultBackend {
+    /// How long results are kept unless told otherwise.
+    pub const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+    pub fn generate_key(id: &Uuid) -> String {
+        format!("toretsu:result:{id}")
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.set_ttl(ttl);
+        self
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl ResultBackend {
+    pub fn new(client: Client) -> Self {
+        Self {
+            ttl: Self::TTL,
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    /// Stores how a task went, replacing anything already stored for it.
+    pub fn store<O: Serialize>(
+        &self,
+        id: Uuid,
+        outcome: std::result::Result<&O, &str>,
+    ) -> Result<()> {
+        let raw = serde_json::to_string(&outcome)?;
+        let ttl = usize::try_from(self.ttl.as_millis())
+            .unwrap_or(usize::MAX)
+            .max(1);
+        let mut client = self.client.lock().unwrap();
+        Ok(client
+            .connection
+            .pset_ex(Self::generate_key(&id), raw, ttl)?)
+    }
+
+    /// The output of the task, `None` if it hasn't finished or its result
+    /// has expired, or an [`Error::Task`] if it failed.
+    pub fn fetch<O: DeserializeOwned>(&self, id: Uuid) -> Result<Option<O>> {
+        let mut client = self.client.lock().unwrap();
+        let raw: Option<String> = client.connection.get(Self::generate_key(&id))?;
+        drop(client);
+
+        let Some(raw) = raw else {
+            return Ok(None);
+        };
+        match serde_json::from_str::<std::result::Result<O, String>>(&raw)? {
+            Ok(output) => Ok(Some(output)),
+            Err(err) => Err(Error::Task(err)),
+        }
+    }
+
+    /// Polls for the task's result until it turns up or `timeout` passes.
+    pub fn wait<O: DeserializeOwned>(&self, id: Uuid, timeout: Duration) -> Result<Option<O>> {
+        let until = Instant::now() + timeout;
+        loop {
+            if let Some(output) = self.fetch(id)? {
+                return Ok(Some(output));
+            }
+
+            let now = Instant::now();
+            if now >= until {
+                return Ok(None);
+            }
+            thread::sleep((until - now).min(POLL));
+        }
+    }
+
+    /// Deletes the task's result, returning whether there was one.
+    pub fn forget(&self, id: Uuid) -> Result<bool> {
+        let mut client = self.client.lock().unwrap();
+        let removed: usize = client.connection.del(Self::generate_key(&id))?;
+
+        Ok(removed > 0)
+    }
+}
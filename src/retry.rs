@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+/// How often, and how patiently, a failed task is retried. Delays grow
+/// exponentially from `base_delay`, capped at `max_delay`, and with `jitter`
+/// each one is drawn from the upper half of that window so tasks that failed
+/// together don't all retry together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+impl RetryPolicy {
+    pub fn init(
+        max_attempts: u32,
+        base_delay: Duration,
+        max_delay: Duration,
+        jitter: bool,
+    ) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            max_delay,
+            jitter,
+        }
+    }
+
+    pub fn new(max_attempts: u32) -> Self {
+        let base_delay = Duration::from_millis(100);
+        let max_delay = Duration::from_secs(60);
+
+        Self::init(max_attempts, base_delay, max_delay, true)
+    }
+
+    /// Runs each task exactly once.
+    pub fn never() -> Self {
+        Self::init(1, Duration::ZERO, Duration::ZERO, false)
+    }
+
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn should_retry(&self, attempts: u32) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// How long to wait after the given number of failed attempts.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        match self.jitter {
+            true => {
+                let half = delay / 2;
+                half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+            }
+            false => delay,
+        }
+    }
+}
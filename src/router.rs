@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use redis::{ControlFlow, Msg};
+
+type Handler = Box<dyn FnMut(&Msg) -> ControlFlow<()> + Send>;
+
+/// Sends messages from several channels and patterns to different handlers,
+/// so one subscription can feed several workers. Start it with
+/// [`Client::route`](crate::client::Client::route).
+///
+/// Messages are matched by the subscription that delivered them: a channel
+/// route gets what was published to exactly that channel, and a pattern
+/// route (e.g. `orders.*`) gets everything Redis matched against it. A
+/// handler that returns `ControlFlow::Break` is removed, and the router
+/// stops once none are left.
+#[derive(Default)]
+pub struct Router {
+    channels: HashMap<String, Handler>,
+    patterns: HashMap<String, Handler>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn route<F>(&mut self, channel: &str, handler: F) -> &mut Self
+    where
+        F: FnMut(&Msg) -> ControlFlow<()> + Send + 'static,
+    {
+        self.channels.insert(channel.to_string(), Box::new(handler));
+        self
+    }
+
+    pub fn route_pattern<F>(&mut self, pattern: &str, handler: F) -> &mut Self
+    where
+        F: FnMut(&Msg) -> ControlFlow<()> + Send + 'static,
+    {
+        self.patterns.insert(pattern.to_string(), Box::new(handler));
+        self
+    }
+
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.channels.keys().map(String::as_str)
+    }
+
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.patterns.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Hands `msg` to the handler for the channel or pattern it arrived on.
+    /// Messages nothing is routed to are ignored.
+    pub fn dispatch(&mut self, msg: &Msg) -> ControlFlow<()> {
+        let (routes, key) = match msg.from_pattern() {
+            true => match msg.get_pattern::<String>() {
+                Ok(pattern) => (&mut self.patterns, pattern),
+                Err(_) => return ControlFlow::Continue,
+            },
+            false => (&mut self.channels, msg.get_channel_name().to_string()),
+        };
+
+        if let Some(handler) = routes.get_mut(&key) {
+            if let ControlFlow::Break(()) = handler(msg) {
+                routes.remove(&key);
+            }
+        }
+
+        match self.is_empty() {
+            true => ControlFlow::Break(()),
+            false => ControlFlow::Continue,
+        }
+    }
+}
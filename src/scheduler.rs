@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeDelta, Timelike, Utc};
+use redis::{Commands, Script};
+use serde::Serialize;
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::listener::Listener;
+use crate::worker::Worker;
+
+// The longest the scheduler sleeps before looking at the clock again, so a
+// clock that jumps doesn't leave it asleep.
+const IDLE: Duration = Duration::from_secs(1);
+
+// Claims a tick for a job by moving its last run forward, so only the first
+// scheduler to get there fires it.
+const CLAIM: &str = r"
+local last = tonumber(redis.call('HGET', KEYS[1], ARGV[1]) or '0')
+if last >= tonumber(ARGV[2]) then
+    return 0
+end
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+return 1
+";
+
+// Milliseconds since the Unix epoch, as scores and file names keep times;
+// anything earlier counts as the epoch itself.
+pub(crate) fn millis(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+/// A standard five-field cron expression - minute, hour, day of month, month
+/// and day of week - evaluated in UTC. Fields take `*`, single values,
+/// ranges, lists and steps (`*/15`, `1-5`, `0,30`, `9-17/2`), days of the
+/// week run 0-7 with both ends meaning Sunday, and `@hourly`, `@daily`,
+/// `@weekly`, `@monthly` and `@yearly` are accepted as shorthands. As in
+/// Vixie cron, restricting both day fields matches days that satisfy either.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn field(field: &str, min: u32, max: u32) -> Option<u64> {
+        let mut bits = 0;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step.parse::<u32>().ok()?)),
+                None => (part, None),
+            };
+            if step == Some(0) {
+                return None;
+            }
+
+            let (low, high) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((low, high)) => (low.parse().ok()?, high.parse().ok()?),
+                None => {
+                    let value = range.parse().ok()?;
+                    match step {
+                        Some(_) => (value, max),
+                        None => (value, value),
+                    }
+                }
+            };
+            if low < min || high > max || low > high {
+                return None;
+            }
+
+            for value in (low..=high).step_by(step.unwrap_or(1) as usize) {
+                bits |= 1 << value;
+            }
+        }
+
+        Some(bits)
+    }
+
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+        let invalid = || Error::Schedule(format!("can't parse cron expression `{expression}`"));
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid());
+        };
+
+        let mut weekdays = Self::field(weekday, 0, 7).ok_or_else(invalid)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: Self::field(minute, 0, 59).ok_or_else(invalid)?,
+            hours: Self::field(hour, 0, 23).ok_or_else(invalid)?,
+            days: Self::field(day, 1, 31).ok_or_else(invalid)?,
+            months: Self::field(month, 1, 12).ok_or_else(invalid)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, at: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << at.day()) != 0;
+        let weekday = self.weekdays & (1 << at.weekday().num_days_from_sunday()) != 0;
+
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// The first minute strictly after `after` that matches, if there's one
+    /// in the next five years.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let after = DateTime::<Utc>::from(after).naive_utc();
+        let mut at = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let limit = at + TimeDelta::days(5 * 366);
+
+        while at < limit {
+            if self.months & (1 << at.month()) == 0 {
+                let (year, month) = match at.month() {
+                    12 => (at.year() + 1, 1),
+                    month => (at.year(), month + 1),
+                };
+                at = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(&at) {
+                at = at.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << at.hour()) == 0 {
+                at = at.with_minute(0)? + TimeDelta::hours(1);
+            } else if self.minutes & (1 << at.minute()) == 0 {
+                at += TimeDelta::minutes(1);
+            } else {
+                return Some(SystemTime::from(at.and_utc()));
+            }
+        }
+
+        None
+    }
+}
+
+impl FromStr for Cron {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        Self::parse(expression)
+    }
+}
+
+/// When a scheduled job fires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every so often, on multiples of the interval since the Unix epoch so
+    /// separate schedulers agree on the ticks.
+    Interval(Duration),
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// Fires every `interval`, counted in whole milliseconds and never less
+    /// than one.
+    pub fn every(interval: Duration) -> Self {
+        Self::Interval(interval.max(Duration::from_millis(1)))
+    }
+
+    pub fn cron(expression: &str) -> Result<Self> {
+        Ok(Self::Cron(Cron::parse(expression)?))
+    }
+
+    /// The first tick strictly after `after`.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        match self {
+            Self::Interval(interval) => {
+                let interval = interval.as_millis().max(1) as u64;
+                let tick = (millis(after) / interval + 1) * interval;
+                Some(UNIX_EPOCH + Duration::from_millis(tick))
+            }
+            Self::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+type Fire = Box<dyn FnMut() -> Result<()> + Send>;
+
+struct Job {
+    name: String,
+    schedule: Schedule,
+    fire: Fire,
+    next: Option<SystemTime>,
+    last_run: Option<SystemTime>,
+}
+
+/// Fires jobs on cron expressions or fixed intervals, typically to queue a
+/// task on a [`Worker`] or send one to other processes, and runs alongside
+/// the workers with [`Scheduler::start`].
+///
+/// On its own a scheduler only knows about itself. Given a [`Client`] it
+/// records each job's last run in Redis and claims every tick there before
+/// firing it, so any number of schedulers with the same jobs can run and
+/// each tick still fires once. Ticks missed while no scheduler was running
+/// are skipped rather than caught up.
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<Job>,
+    client: Option<Client>,
+    claim: Option<Script>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn generate_key() -> String {
+        String::from("toretsu:schedule")
+    }
+
+    /// Shares this scheduler's ticks with every other scheduler on the same
+    /// Redis.
+    pub fn set_client(&mut self, client: Client) {
+        self.client = Some(client);
+        self.claim = Some(Script::new(CLAIM));
+    }
+
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.set_client(client);
+        self
+    }
+
+    /// Calls `fire` on every tick of `schedule`, replacing any job already
+    /// called `name`. Names identify a job across schedulers, so give the
+    /// same job the same name everywhere.
+    pub fn add<F>(&mut self, name: &str, schedule: Schedule, fire: F) -> &mut Self
+    where
+        F: FnMut() -> Result<()> + Send + 'static,
+    {
+        self.jobs.retain(|job| job.name != name);
+        self.jobs.push(Job {
+            name: name.to_string(),
+            next: schedule.next_after(SystemTime::now()),
+            schedule,
+            fire: Box::new(fire),
+            last_run: None,
+        });
+        self
+    }
+
+    /// Queues a task made by `template` on `worker` on every tick, for as
+    /// long as the worker is around.
+    pub fn enqueue<T, F>(
+        &mut self,
+        name: &str,
+        schedule: Schedule,
+        worker: &Worker<T>,
+        mut template: F,
+    ) -> &mut Self
+    where
+        T: Ord + Send + 'static,
+        F: FnMut() -> T + Send + 'static,
+    {
+        let sender = worker.sender();
+        self.add(name, schedule, move || sender(template()))
+    }
+
+    /// Sends a task made by `template` to `channel` with [`Client::send`] on
+    /// every tick.
+    pub fn send<T, F>(
+        &mut self,
+        name: &str,
+        schedule: Schedule,
+        mut client: Client,
+        channel: &str,
+        mut template: F,
+    ) -> &mut Self
+    where
+        T: Serialize,
+        F: FnMut() -> T + Send + 'static,
+    {
+        let channel = channel.to_string();
+        self.add(name, schedule, move || {
+            client.send(&channel, &template()).map(|_| ())
+        })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.jobs.iter().map(|job| job.name.as_str())
+    }
+
+    /// When this scheduler last fired the job called `name`.
+    pub fn last_run(&self, name: &str) -> Option<SystemTime> {
+        self.jobs
+            .iter()
+            .find(|job| job.name == name)
+            .and_then(|job| job.last_run)
+    }
+
+    /// When the job called `name` is next due.
+    pub fn next_run(&self, name: &str) -> Option<SystemTime> {
+        self.jobs
+            .iter()
+            .find(|job| job.name == name)
+            .and_then(|job| job.next)
+    }
+
+    // Claims `tick` for the job called `name`, replacing the connection if
+    // it turns out to be lost so the next try can succeed.
+    fn claim(&mut self, name: &str, tick: SystemTime) -> Result<bool> {
+        let (client, claim) = match self.client.as_mut().zip(self.claim.as_ref()) {
+            Some(shared) => shared,
+            None => return Ok(true),
+        };
+
+        let claimed: redis::RedisResult<bool> = claim
+            .key(Self::generate_key())
+            .arg(name)
+            .arg(millis(tick))
+            .invoke(&mut client.connection);
+
+        match claimed {
+            Ok(claimed) => Ok(claimed),
+            Err(err) => {
+                let err = Error::from(err);
+                if err.is_connection() {
+                    if let Ok(connection) = client.redis.get_connection() {
+                        client.connection = connection;
+                    }
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Fires every job that's due, returning how many fired. A job whose
+    /// tick can't be claimed is tried again on the next call. Errors from
+    /// claiming or firing don't stop the other jobs; the first is returned.
+    pub fn run_pending(&mut self) -> Result<usize> {
+        let now = SystemTime::now();
+        let mut fired = 0;
+        let mut failed = None;
+
+        for index in 0..self.jobs.len() {
+            let tick = match self.jobs[index].next {
+                Some(tick) if tick <= now => tick,
+                _ => continue,
+            };
+
+            let name = self.jobs[index].name.clone();
+            let claimed = match self.claim(&name, tick) {
+                Ok(claimed) => claimed,
+                Err(err) => {
+                    failed.get_or_insert(err);
+                    continue;
+                }
+            };
+
+            let job = &mut self.jobs[index];
+            job.next = job.schedule.next_after(now);
+            if claimed {
+                job.last_run = Some(tick);
+                fired += 1;
+                if let Err(err) = (job.fire)() {
+                    failed.get_or_insert(err);
+                }
+            }
+        }
+
+        match failed {
+            Some(err) => Err(err),
+            None => Ok(fired),
+        }
+    }
+
+    fn run(mut self, stop: &AtomicBool) -> Result<()> {
+        while !stop.load(Ordering::SeqCst) {
+            let _ = self.run_pending();
+
+            let now = SystemTime::now();
+            let wait = self
+                .jobs
+                .iter()
+                .filter_map(|job| job.next)
+                .min()
+                .map_or(IDLE, |next| {
+                    next.duration_since(now).unwrap_or_default().min(IDLE)
+                });
+            if !Listener::pause(stop, wait) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fires jobs on a background thread until the returned [`Listener`] is
+    /// stopped.
+    pub fn start(self) -> Listener {
+        Listener::start("toretsu-scheduler", move |stop| self.run(stop))
+    }
+
+    /// Every job's last run as recorded in Redis by schedulers sharing it.
+    #[cfg(not(tarpaulin_include))]
+    pub fn last_runs(client: &mut Client) -> Result<HashMap<String, SystemTime>> {
+        let runs: HashMap<String, u64> = client.connection.hgetall(Self::generate_key())?;
+        let runs = runs
+            .into_iter()
+            .map(|(name, at)| (name, UNIX_EPOCH + Duration::from_millis(at)))
+            .collect();
+
+        Ok(runs)
+    }
+}
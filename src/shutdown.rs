@@ -0,0 +1,30 @@
+use uuid::Uuid;
+
+use crate::entry::Entry;
+
+/// What became of a worker's tasks when it was shut down with
+/// [`Worker::shutdown`](crate::worker::Worker::shutdown).
+#[derive(Debug)]
+pub struct ShutdownReport<T> {
+    /// How many of the tasks running at the time finished before the
+    /// deadline, successfully or not.
+    pub finished: usize,
+    /// The tasks still running at the deadline, by id. They're cancelled, and
+    /// when they stop they're left unacknowledged and in the worker's backup,
+    /// so a persistent worker or a stream or broker hands them out again. An
+    /// in-memory worker's are lost unless they finish anyway.
+    pub interrupted: Vec<Uuid>,
+    /// The tasks that never started, highest priority first: those still
+    /// queued, those handed to a thread that hadn't picked them up, and those
+    /// waiting out a delay. A persistent worker's are still in Redis too, and
+    /// ones read from a stream or broker are unacknowledged, so they come
+    /// back after a restart; the rest can be assigned to another worker.
+    pub pending: Vec<Entry<T>>,
+}
+
+impl<T> ShutdownReport<T> {
+    /// Whether every task that was running got to finish.
+    pub fn is_clean(&self) -> bool {
+        self.interrupted.is_empty()
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use redis::streams::{StreamId, StreamRangeReply, StreamReadOptions, StreamReadReply};
+use redis::{Commands, Connection, Value};
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::listener::Listener;
+
+/// The stream field a task is stored under.
+pub const FIELD: &str = "task";
+
+// How long a read blocks before the consumer checks whether it's been asked
+// to stop.
+const POLL: Duration = Duration::from_millis(250);
+
+/// A consumer group reading a channel's stream. Every worker consuming with
+/// the same group name shares its tasks, each one delivered to a single
+/// worker and kept pending until that worker acknowledges it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsumerGroup {
+    pub name: String,
+    /// How many tasks a worker holds in its queue before it stops reading.
+    pub batch: usize,
+    /// How long a task can go unacknowledged before another worker claims
+    /// it. Keep this above the longest a task takes, retries included.
+    pub min_idle: Duration,
+}
+
+impl ConsumerGroup {
+    pub fn init(name: &str, batch: usize, min_idle: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            batch: batch.max(1),
+            min_idle,
+        }
+    }
+
+    pub fn new(name: &str) -> Self {
+        Self::init(name, 16, Duration::from_secs(60))
+    }
+
+    pub fn generate_key(channel: &str) -> String {
+        format!("toretsu:{channel}:stream")
+    }
+
+    pub fn with_batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+
+    pub fn with_min_idle(mut self, min_idle: Duration) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+}
+
+struct Acker {
+    key: String,
+    group: String,
+    client: Mutex<Client>,
+    // Entries handed to the worker and not yet acknowledged, until they are. The worker's
+    // own entries go idle while they wait in its queue, so claiming can
+    // hand them back; these are skipped rather than queued twice.
+    held: Mutex<HashSet<String>>,
+}
+
+/// A stream entry handed to a consumer, which stays pending in its group
+/// until it's acknowledged.
+#[derive(Clone)]
+pub(crate) struct Delivery {
+    pub id: String,
+    acker: Arc<Acker>,
+}
+
+impl fmt::Debug for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Delivery").field("id", &self.id).finish()
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Delivery {
+    /// Acknowledges the entry and deletes it from the stream, so the stream
+    /// only holds tasks that are yet to be done.
+    pub fn ack(&self) -> Result<()> {
+        let acker = &self.acker;
+        let mut client = acker.client.lock().unwrap();
+        let acked: redis::RedisResult<()> = redis::pipe()
+            .atomic()
+            .xack(&acker.key, &acker.group, &[&self.id])
+            .ignore()
+            .xdel(&acker.key, &[&self.id])
+            .ignore()
+            .query(&mut client.connection);
+        drop(client);
+
+        // Whether or not it went through, the worker is done with the entry.
+        // If it's still pending, it can be claimed again like any other.
+        acker.held.lock().unwrap().remove(&self.id);
+        Ok(acked?)
+    }
+}
+
+pub(crate) struct Message {
+    pub payload: Vec<u8>,
+    pub delivery: Delivery,
+}
+
+struct Consumer {
+    key: String,
+    group: ConsumerGroup,
+    name: String,
+    acker: Arc<Acker>,
+}
+
+#[cfg(not(tarpaulin_include))]
+impl Consumer {
+    fn connect(client: &Client) -> Result<Connection> {
+        let connection = client.redis.get_connection()?;
+        connection.set_read_timeout(Some(POLL * 8))?;
+
+        Ok(connection)
+    }
+
+    fn create_group(&self, connection: &mut Connection) -> Result<()> {
+        let created: redis::RedisResult<()> =
+            connection.xgroup_create_mkstream(&self.key, &self.group.name, "0");
+        match created {
+            Err(err) if err.code() != Some("BUSYGROUP") => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn messages(&self, ids: Vec<StreamId>) -> Vec<Message> {
+        let mut held = self.acker.held.lock().unwrap();
+        let fresh: Vec<StreamId> = ids
+            .into_iter()
+            .filter(|entry| held.insert(entry.id.clone()))
+            .collect();
+        drop(held);
+
+        fresh
+            .into_iter()
+            .filter_map(|entry| {
+                let delivery = Delivery {
+                    id: entry.id,
+                    acker: Arc::clone(&self.acker),
+                };
+
+                match entry.map.get(FIELD) {
+                    Some(Value::Data(payload)) => Some(Message {
+                        payload: payload.clone(),
+                        delivery,
+                    }),
+                    // An entry without a task can never be processed.
+                    _ => {
+                        let _ = delivery.ack();
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // Reads new entries when `id` is ">", and otherwise this consumer's own
+    // pending entries after `id` - those a previous run with the same name
+    // never acknowledged.
+    fn read(&self, connection: &mut Connection, id: &str, count: usize) -> Result<Vec<Message>> {
+        let mut options = StreamReadOptions::default()
+            .group(&self.group.name, &self.name)
+            .count(count);
+        if id == ">" {
+            options = options.block(POLL.as_millis() as usize);
+        }
+
+        let reply: Option<StreamReadReply> =
+            connection.xread_options(&[&self.key], &[id], &options)?;
+        let ids = reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .collect();
+
+        Ok(self.messages(ids))
+    }
+
+    // Takes over entries other consumers have left pending for longer than
+    // the group's `min_idle`, returning them with the cursor to resume from.
+    fn claim(
+        &self,
+        connection: &mut Connection,
+        cursor: &str,
+        count: usize,
+    ) -> Result<(String, Vec<Message>)> {
+        let reply: Vec<Value> = redis::cmd("XAUTOCLAIM")
+            .arg(&self.key)
+            .arg(&self.group.name)
+            .arg(&self.name)
+            .arg(self.group.min_idle.as_millis() as u64)
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(count)
+            .query(connection)?;
+
+        let mut reply = reply.into_iter();
+        let cursor: String = match reply.next() {
+            Some(value) => redis::from_redis_value(&value)?,
+            None => String::from("0-0"),
+        };
+        let claimed: StreamRangeReply = match reply.next() {
+            Some(value) => redis::from_redis_value(&value)?,
+            None => StreamRangeReply::default(),
+        };
+
+        Ok((cursor, self.messages(claimed.ids)))
+    }
+
+    fn run<F>(
+        &self,
+        client: Client,
+        mut connection: Connection,
+        stop: &AtomicBool,
+        sink: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Vec<Message>) -> ControlFlow<(), usize>,
+    {
+        let mut backlog = Some(String::from("0"));
+        let mut cursor = String::from("0-0");
+        let mut claimed_at: Option<Instant> = None;
+
+        let fetch = |room| {
+            let claiming = claimed_at.is_none_or(|at| at.elapsed() >= self.group.min_idle);
+            let read = match (&backlog, claiming) {
+                (Some(after), _) => self.read(&mut connection, after, room),
+                (None, true) => match self.claim(&mut connection, &cursor, room) {
+                    Ok((next, messages)) => {
+                        if next == "0-0" {
+                            claimed_at = Some(Instant::now());
+                        }
+                        cursor = next;
+                        Ok(messages)
+                    }
+                    Err(err) => Err(err),
+                },
+                (None, false) => self.read(&mut connection, ">", room),
+            };
+
+            let messages = match read {
+                Ok(messages) => messages,
+                Err(err) => {
+                    let reconnect = || {
+                        let mut connection = Self::connect(&client)?;
+                        self.create_group(&mut connection)?;
+                        Ok(connection)
+                    };
+                    if let Some(reconnected) = Listener::reconnect(stop, err, reconnect)? {
+                        connection = reconnected;
+                    }
+                    return Ok(Vec::new());
+                }
+            };
+
+            if backlog.is_some() {
+                backlog = messages.last().map(|message| message.delivery.id.clone());
+            }
+
+            Ok(messages)
+        };
+
+        Listener::fill(stop, fetch, sink)
+    }
+}
+
+/// Starts reading `channel`'s stream as `consumer` in `group`, creating the
+/// stream and group if need be. `sink` is handed each batch of entries and
+/// returns how many more it has room for.
+#[cfg(not(tarpaulin_include))]
+pub(crate) fn consume<F>(
+    client: &Client,
+    channel: &str,
+    group: ConsumerGroup,
+    consumer: String,
+    sink: F,
+) -> Result<Listener>
+where
+    F: FnMut(Vec<Message>) -> ControlFlow<(), usize> + Send + 'static,
+{
+    let key = ConsumerGroup::generate_key(channel);
+    let acker = Acker {
+        key: key.clone(),
+        group: group.name.clone(),
+        client: Mutex::new(Client::try_init(client.config.clone())?),
+        held: Mutex::new(HashSet::new()),
+    };
+    let consumer = Consumer {
+        key,
+        group,
+        name: consumer,
+        acker: Arc::new(acker),
+    };
+
+    let reader = Client::try_init(client.config.clone())?;
+    let mut connection = Consumer::connect(&reader)?;
+    consumer.create_group(&mut connection)?;
+
+    let listener = Listener::start("toretsu-consumer", move |stop| {
+        consumer.run(reader, connection, stop, sink)
+    });
+
+    Ok(listener)
+}
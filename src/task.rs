@@ -1,3 +1,82 @@
+use std::future::Future;
+use std::time::Duration;
+
+use crate::context::TaskContext;
+use crate::retry::RetryPolicy;
+
+pub type Failure = Box<dyn std::error::Error + Send + Sync>;
+
 pub trait Task {
     fn process(&mut self);
+
+    /// The fallible counterpart to `process`. Tasks that can fail implement
+    /// this and have `process` discard its result; an `Err` (or a panic) is
+    /// retried per the retry policy.
+    fn try_process(&mut self) -> Result<(), Failure> {
+        self.process();
+        Ok(())
+    }
+
+    /// What a worker actually calls. Tasks that should give up when they're
+    /// cancelled or run out of time implement this and check
+    /// `ctx.is_cancelled()` as they go.
+    fn try_process_with(&mut self, _ctx: &TaskContext) -> Result<(), Failure> {
+        self.try_process()
+    }
+
+    /// Overrides the worker's retry policy for this task.
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        None
+    }
+
+    /// Overrides the worker's timeout for this task.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The priority a worker reports in the task's [`TaskContext`], for
+    /// tasks that have one.
+    fn priority(&self) -> Option<i64> {
+        None
+    }
+}
+
+/// A task whose work is mostly waiting on I/O. Run on a tokio runtime (see
+/// `Worker::new_async`, behind the `tokio` feature), so thousands can be in
+/// flight without a thread each. Implementations can use `async fn`.
+pub trait AsyncTask: Send {
+    fn process(&mut self) -> impl Future<Output = ()> + Send;
+
+    /// The fallible counterpart to `process`; see [`Task::try_process`].
+    fn try_process(&mut self) -> impl Future<Output = Result<(), Failure>> + Send {
+        async {
+            self.process().await;
+            Ok(())
+        }
+    }
+
+    /// What a worker actually calls; see [`Task::try_process_with`].
+    fn try_process_with(
+        &mut self,
+        _ctx: &TaskContext,
+    ) -> impl Future<Output = Result<(), Failure>> + Send {
+        self.try_process()
+    }
+
+    /// Overrides the worker's retry policy for this task.
+    fn retry_policy(&self) -> Option<RetryPolicy> {
+        None
+    }
+
+    /// Overrides the worker's timeout for this task. Unlike a [`Task`], an
+    /// async task that runs out of time is dropped at its next `.await`.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The priority a worker reports in the task's context; see
+    /// [`Task::priority`].
+    fn priority(&self) -> Option<i64> {
+        None
+    }
 }
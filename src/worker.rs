@@ -1,14 +1,707 @@
+use std::any::Any;
+use std::cmp::Ordering as Order;
+use std::collections::{BinaryHeap, HashMap};
+#[cfg(feature = "tokio")]
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, Weak};
+#[cfg(feature = "tokio")]
+use std::task::Poll;
+use std::time::{Duration, Instant, SystemTime};
+
 use names::{Generator, Name};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use redis::{ControlFlow, Msg};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+#[cfg(feature = "tokio")]
+use tokio::runtime::Handle;
 use uuid::Uuid;
 
+use crate::backup::Backup;
+use crate::broker::{self, Broker};
+use crate::client::Client;
+use crate::context::{CancellationToken, Logger, TaskContext};
+use crate::control::Command;
+use crate::dead_letter::DeadLetter;
+use crate::distributed::{DistributedQueue, Priority};
+use crate::entry::{Entry, Receipt, Reply};
+use crate::error::{Error, Result};
+use crate::listener::Listener;
+use crate::output::{self, Produce};
 use crate::queue::Queue;
-use crate::task::Task;
+use crate::results::ResultBackend;
+use crate::retry::RetryPolicy;
+use crate::shutdown::ShutdownReport;
+use crate::streams::{self, ConsumerGroup};
+#[cfg(feature = "tokio")]
+use crate::task::AsyncTask;
+use crate::task::{Failure, Task};
+
+// The longest the timer thread sleeps before checking whether its worker
+// has been dropped.
+const TIMER_IDLE: Duration = Duration::from_secs(1);
+
+// An entry waiting for `at` before it goes in the queue. Ordered so the
+// earliest is at the top of a `BinaryHeap`. Scheduled entries of a
+// persistent worker carry their member of the backup's delayed set.
+struct Due<T> {
+    at: Instant,
+    entry: Entry<T>,
+    member: Option<String>,
+}
+
+impl<T> PartialEq for Due<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl<T> Eq for Due<T> {}
+
+impl<T> PartialOrd for Due<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Order> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Due<T> {
+    fn cmp(&self, other: &Self) -> Order {
+        other.at.cmp(&self.at)
+    }
+}
+
+// What a worker does with the output of its tasks once they're done. The
+// task type is only known to be `Produce` where these are made, so they
+// carry what needs it as plain functions.
+struct Outputs<T> {
+    collect: fn(&mut T) -> Option<Output>,
+    result_id: fn(&T) -> Option<Uuid>,
+    results: Option<(ResultBackend, Store)>,
+}
+
+type Output = Box<dyn Any + std::marker::Send>;
+
+type Store = fn(
+    &ResultBackend,
+    Uuid,
+    std::result::Result<&(dyn Any + std::marker::Send), &str>,
+) -> Result<()>;
+
+impl<T> Clone for Outputs<T> {
+    fn clone(&self) -> Self {
+        Self {
+            collect: self.collect,
+            result_id: self.result_id,
+            results: self.results.clone(),
+        }
+    }
+}
+
+impl<T: Produce> Outputs<T> {
+    fn new() -> Self {
+        Self {
+            collect: |task| {
+                let output = task.output()?;
+                Some(Box::new(output))
+            },
+            result_id: T::result_id,
+            results: None,
+        }
+    }
+
+    fn store(
+        backend: &ResultBackend,
+        id: Uuid,
+        outcome: std::result::Result<&(dyn Any + std::marker::Send), &str>,
+    ) -> Result<()>
+    where
+        T::Output: Serialize,
+    {
+        match outcome {
+            Ok(output) => match output.downcast_ref::<T::Output>() {
+                Some(output) => backend.store(id, Ok(output)),
+                None => Ok(()),
+            },
+            Err(err) => backend.store::<T::Output>(id, Err(err)),
+        }
+    }
+}
+
+impl<T> Outputs<T> {
+    // Stores how the task went, if the worker has a result backend.
+    fn keep(
+        &self,
+        entry: &Entry<T>,
+        outcome: std::result::Result<&(dyn Any + std::marker::Send), &str>,
+    ) {
+        if let Some((backend, store)) = &self.results {
+            let id = (self.result_id)(&entry.task).unwrap_or(entry.id);
+            let _ = store(backend, id, outcome);
+        }
+    }
+}
+
+// Told how a task queued through `Worker::submitter` went.
+pub(crate) type Callback<O> =
+    Box<dyn FnOnce(std::result::Result<O, String>) + std::marker::Send + Sync>;
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> Failure {
+    let message = match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => String::from("task panicked"),
+        },
+    };
+
+    format!("task panicked: {message}").into()
+}
+
+struct Shared<T> {
+    queue: Mutex<Queue<Entry<T>>>,
+    delayed: Mutex<BinaryHeap<Due<T>>>,
+    wake: Condvar,
+    timer: AtomicBool,
+    active: AtomicBool,
+    running: AtomicUsize,
+    concurrency: AtomicUsize,
+    retry: RwLock<RetryPolicy>,
+    timeout: RwLock<Option<Duration>>,
+    // The contexts of the attempts running right now, by task id, and
+    // notified as each one settles.
+    attempts: Mutex<HashMap<Uuid, TaskContext>>,
+    settled: Condvar,
+    // Tasks handed to the executor that haven't started yet. The executor
+    // only gets their ids, so a pause or shutdown can take them back.
+    spawned: Mutex<HashMap<Uuid, Entry<T>>>,
+    // Set for good by `Worker::shutdown`.
+    closed: AtomicBool,
+    paused: AtomicBool,
+    logger: RwLock<Option<Logger>>,
+    state: RwLock<Option<Arc<dyn Any + std::marker::Send + Sync>>>,
+    // Copied from the worker for each attempt's context.
+    worker_id: Uuid,
+    channel: String,
+    outputs: RwLock<Option<Outputs<T>>>,
+    dead: Mutex<Vec<DeadLetter<T>>>,
+    executor: RwLock<Arc<dyn Executor<T>>>,
+    backup: Option<Backup<T>>,
+}
+
+impl<T> Shared<T>
+where
+    T: Ord + std::marker::Send + 'static,
+{
+    fn enqueue(self: &Arc<Self>, tasks: Vec<T>) -> Result<()> {
+        self.accepting()?;
+        self.push(tasks.into_iter().map(Entry::new).collect())
+    }
+
+    fn accepting(&self) -> Result<()> {
+        match self.closed.load(Ordering::SeqCst) {
+            true => Err(Error::Closed),
+            false => Ok(()),
+        }
+    }
+
+    // Queues entries once they're in the backup, if there is one. Nothing is
+    // queued if that fails, so a caller can retry without running them twice.
+    fn push(self: &Arc<Self>, mut entries: Vec<Entry<T>>) -> Result<()> {
+        if let Some(backup) = &self.backup {
+            Self::mirror(backup, &mut entries)?;
+        }
+        self.requeue(entries);
+
+        Ok(())
+    }
+
+    // Queues entries that have nowhere else to go, such as ones already taken
+    // off a subscription or a shared queue, backing them up if it can.
+    fn adopt(self: &Arc<Self>, mut entries: Vec<Entry<T>>) {
+        if let Some(backup) = &self.backup {
+            let _ = Self::mirror(backup, &mut entries);
+        }
+        self.requeue(entries);
+    }
+
+    fn mirror(backup: &Backup<T>, entries: &mut [Entry<T>]) -> Result<()> {
+        let raw = entries
+            .iter()
+            .map(|entry| backup.encode(&entry.task))
+            .collect::<serde_json::Result<Vec<String>>>()?;
+        backup.push_raw(&raw)?;
+
+        for (entry, raw) in entries.iter_mut().zip(raw) {
+            entry.raw = Some(raw);
+        }
+
+        Ok(())
+    }
+
+    fn requeue(self: &Arc<Self>, entries: Vec<Entry<T>>) {
+        self.queue.lock().unwrap().extend(entries);
+        self.dispatch();
+    }
+
+    fn next(&self) -> Option<Uuid> {
+        let mut queue = self.queue.lock().unwrap();
+        if !self.active.load(Ordering::SeqCst) || self.paused.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        if self.running.load(Ordering::SeqCst) >= self.concurrency.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let entry = queue.pop()?;
+        let id = entry.id;
+        self.running.fetch_add(1, Ordering::SeqCst);
+        self.spawned.lock().unwrap().insert(id, entry);
+
+        Some(id)
+    }
+
+    // Pulls from the queue until it's empty, the worker is at its concurrency
+    // limit or it clocks out. Every finished task pumps again, so the next
+    // highest-priority task starts as soon as a slot frees up.
+    fn dispatch(self: &Arc<Self>) {
+        let executor = Arc::clone(&self.executor.read().unwrap());
+        while let Some(id) = self.next() {
+            executor.spawn(Arc::clone(self), id);
+        }
+    }
+
+    // Takes a spawned entry back and starts the clock on its attempt, with
+    // the task's own timeout if it has one and the worker's otherwise. `None`
+    // if a pause or a shutdown got there first.
+    fn begin<F, P>(
+        self: &Arc<Self>,
+        id: Uuid,
+        timeout: F,
+        priority: P,
+    ) -> Option<(Entry<T>, TaskContext)>
+    where
+        F: FnOnce(&T) -> Option<Duration>,
+        P: FnOnce(&T) -> Option<i64>,
+    {
+        let mut spawned = self.spawned.lock().unwrap();
+        let mut entry = spawned.remove(&id)?;
+        if self.paused.load(Ordering::SeqCst) {
+            drop(spawned);
+            self.withhold(vec![entry]);
+            return None;
+        }
+
+        entry.attempts += 1;
+        let token = match timeout(&entry.task).or(*self.timeout.read().unwrap()) {
+            Some(timeout) => CancellationToken::with_timeout(timeout),
+            None => CancellationToken::new(),
+        };
+        let ctx = TaskContext {
+            token,
+            task_id: entry.id,
+            attempt: entry.attempts,
+            priority: priority(&entry.task),
+            enqueued_at: entry.enqueued_at,
+            worker_id: self.worker_id,
+            channel: self.channel.clone(),
+            progress: Arc::default(),
+            logger: self.logger.read().unwrap().clone(),
+            state: self.state.read().unwrap().clone(),
+        };
+        self.attempts.lock().unwrap().insert(entry.id, ctx.clone());
+        drop(spawned);
+
+        Some((entry, ctx))
+    }
+
+    // Records how an attempt went, then frees its slot. `policy` is the
+    // task's own retry policy, if it has one.
+    fn settle(
+        self: &Arc<Self>,
+        mut entry: Entry<T>,
+        outcome: std::thread::Result<std::result::Result<(), Failure>>,
+        policy: Option<RetryPolicy>,
+        token: &CancellationToken,
+    ) {
+        self.attempts.lock().unwrap().remove(&entry.id);
+        let failure = match outcome {
+            Ok(Ok(())) => None,
+            Ok(Err(failure)) => Some(failure),
+            Err(payload) => Some(panic_message(payload)),
+        };
+
+        if token.was_cancelled() && self.closed.load(Ordering::SeqCst) {
+            return self.abandon(entry);
+        }
+
+        // An attempt that was cancelled or ran out of time fails however it
+        // returned. Only the timeout is worth retrying.
+        let (failure, retry) = if token.was_cancelled() {
+            (Some(Failure::from("task was cancelled")), false)
+        } else if token.is_expired() {
+            let timeout = token.timeout().unwrap_or_default();
+            (
+                Some(format!("task timed out after {timeout:?}").into()),
+                true,
+            )
+        } else {
+            (failure, true)
+        };
+
+        // So does one that returns without producing its output.
+        let (failure, output) = match failure {
+            Some(failure) => (Some(failure), None),
+            None => match self.collect(&mut entry) {
+                Ok(output) => (None, output),
+                Err(failure) => (Some(failure), None),
+            },
+        };
+
+        match failure {
+            Some(failure) => {
+                let policy = match policy {
+                    Some(policy) => policy,
+                    None => *self.retry.read().unwrap(),
+                };
+
+                match retry && policy.should_retry(entry.attempts) {
+                    true => {
+                        let delay = policy.backoff(entry.attempts);
+                        self.defer(entry, delay)
+                    }
+                    false => self.bury(entry, failure.to_string()),
+                }
+            }
+            None => self.finish(entry, output),
+        }
+
+        self.release();
+    }
+
+    // Lets go of an entry a shutdown cut short as if it never ran, leaving
+    // it for its backup, stream or broker to hand out again.
+    fn abandon(self: &Arc<Self>, entry: Entry<T>) {
+        drop(entry);
+        self.release();
+    }
+
+    fn pause(self: &Arc<Self>, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+        match paused {
+            true => self.withhold(self.unspawn()),
+            false => self.dispatch(),
+        }
+    }
+
+    // Puts entries that were spawned but didn't get to start back in the
+    // queue, where they keep their place, and frees their slots.
+    fn withhold(self: &Arc<Self>, entries: Vec<Entry<T>>) {
+        let withheld = entries.len();
+        self.queue.lock().unwrap().extend(entries);
+        self.release_many(withheld);
+    }
+
+    // Takes back every entry that was spawned but hasn't started.
+    fn unspawn(&self) -> Vec<Entry<T>> {
+        let mut spawned = self.spawned.lock().unwrap();
+        spawned.drain().map(|(_, entry)| entry).collect()
+    }
+
+    // Frees an attempt's slot for the next task.
+    fn release(self: &Arc<Self>) {
+        self.release_many(1);
+    }
+
+    fn release_many(self: &Arc<Self>, slots: usize) {
+        self.running.fetch_sub(slots, Ordering::SeqCst);
+        drop(self.attempts.lock().unwrap());
+        self.settled.notify_all();
+        self.dispatch();
+    }
+
+    // Takes the output out of a task that succeeded, if anything is waiting
+    // on it: a handle, a workflow or a result backend. Only then is there
+    // being none a failure.
+    fn collect(&self, entry: &mut Entry<T>) -> std::result::Result<Option<Output>, Failure> {
+        let outputs = self.outputs.read().unwrap();
+        let outputs = match outputs.as_ref() {
+            Some(outputs) if entry.reply.is_some() || outputs.results.is_some() => outputs,
+            _ => return Ok(None),
+        };
+
+        match (outputs.collect)(&mut entry.task) {
+            Some(output) => Ok(Some(output)),
+            None => Err(Failure::from("task produced no output")),
+        }
+    }
+
+    fn finish(&self, mut entry: Entry<T>, output: Option<Output>) {
+        let outputs = self.outputs.read().unwrap().clone();
+        if let Some((outputs, output)) = outputs.zip(output) {
+            outputs.keep(&entry, Ok(&*output));
+            if let Some(reply) = entry.reply.take() {
+                reply.send(Ok(output));
+            }
+        }
+
+        if let Some((backup, raw)) = self.backup.as_ref().zip(entry.raw.as_ref()) {
+            let _ = backup.remove(raw);
+        }
+
+        // Left pending if this fails, so the task is claimed and run again.
+        if let Some(receipt) = &entry.receipt {
+            let _ = receipt.ack();
+        }
+    }
+
+    fn bury(&self, mut entry: Entry<T>, error: String) {
+        if let Some(outputs) = self.outputs.read().unwrap().as_ref() {
+            outputs.keep(&entry, Err(&error));
+        }
+        let reply = entry.reply.take();
+
+        // The dead letter takes over from the stream, which would otherwise
+        // hand the task out again forever.
+        if let Some(receipt) = &entry.receipt {
+            let _ = receipt.ack();
+        }
+
+        let mut letter = DeadLetter::new(entry, error.clone());
+        if let Some((backup, raw)) = self.backup.as_ref().zip(letter.raw.as_ref()) {
+            match letter.encode_record(raw) {
+                Ok(record) => {
+                    if backup.bury(raw, &record).is_ok() {
+                        letter.record = Some(record);
+                    }
+                }
+                Err(_) => {
+                    let _ = backup.remove(raw);
+                }
+            }
+        }
+
+        self.dead.lock().unwrap().push(letter);
+
+        // Answered last, so whoever's waiting on it finds the dead letter.
+        if let Some(reply) = reply {
+            reply.send(Err(error));
+        }
+    }
+
+    fn revive(self: &Arc<Self>, letters: Vec<DeadLetter<T>>) -> Result<usize> {
+        let mut entries = Vec::with_capacity(letters.len());
+        let mut failed = None;
+        let mut letters = letters.into_iter();
+        for letter in letters.by_ref() {
+            if let Some(((backup, record), raw)) = self
+                .backup
+                .as_ref()
+                .zip(letter.record.as_ref())
+                .zip(letter.raw.as_ref())
+            {
+                if let Err(err) = backup.exhume(record, raw) {
+                    failed = Some((letter, err));
+                    break;
+                }
+            }
+
+            entries.push(letter.into_entry());
+        }
+
+        let revived = entries.len();
+        self.requeue(entries);
+
+        match failed {
+            Some((letter, err)) => {
+                let mut dead = self.dead.lock().unwrap();
+                dead.push(letter);
+                dead.extend(letters);
+                Err(err)
+            }
+            None => Ok(revived),
+        }
+    }
+
+    // Holds an entry back for `delay`, then returns it to the queue. Its
+    // Redis copy stays put meanwhile, so a restart doesn't lose it.
+    fn defer(self: &Arc<Self>, entry: Entry<T>, delay: Duration) {
+        self.hold(entry, delay, None);
+    }
+
+    // Holds a new task back until `at`, keeping it in the backup's delayed
+    // set rather than its queue list until then.
+    fn schedule(self: &Arc<Self>, task: T, at: SystemTime) -> Result<()> {
+        self.accepting()?;
+        let mut entry = Entry::new(task);
+        let member = match &self.backup {
+            Some(backup) => Some(Self::delay(backup, &mut entry, at)?),
+            None => None,
+        };
+        let delay = at.duration_since(SystemTime::now()).unwrap_or_default();
+        self.hold(entry, delay, member);
+
+        Ok(())
+    }
+
+    fn delay(backup: &Backup<T>, entry: &mut Entry<T>, at: SystemTime) -> Result<String> {
+        let raw = backup.encode(&entry.task)?;
+        let member = backup.delay(&raw, at)?;
+        entry.raw = Some(raw);
+
+        Ok(member)
+    }
+
+    fn hold(self: &Arc<Self>, entry: Entry<T>, delay: Duration, member: Option<String>) {
+        let at = Instant::now() + delay;
+        self.delayed.lock().unwrap().push(Due { at, entry, member });
+        self.wake.notify_one();
+
+        if !self.timer.swap(true, Ordering::SeqCst) {
+            let shared = Arc::downgrade(self);
+            let timer = std::thread::Builder::new().name(String::from("toretsu-timer"));
+            if timer.spawn(move || Self::tick(shared)).is_err() {
+                self.timer.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn tick(shared: Weak<Self>) {
+        while let Some(shared) = shared.upgrade() {
+            let mut delayed = shared.delayed.lock().unwrap();
+            let now = Instant::now();
+
+            let mut due = Vec::new();
+            while delayed.peek().is_some_and(|next| next.at <= now) {
+                due.extend(delayed.pop());
+            }
+
+            if !due.is_empty() {
+                drop(delayed);
+                let entries = due
+                    .into_iter()
+                    .map(|due| {
+                        if let Some((backup, member)) = shared.backup.as_ref().zip(due.member) {
+                            let _ = backup.promote(&member);
+                        }
+                        due.entry
+                    })
+                    .collect();
+                shared.requeue(entries);
+                continue;
+            }
+
+            let wait = match delayed.peek() {
+                Some(next) => (next.at - now).min(TIMER_IDLE),
+                None => TIMER_IDLE,
+            };
+            drop(shared.wake.wait_timeout(delayed, wait).unwrap());
+        }
+    }
+}
+
+// Where a worker's tasks run. Each executor knows how to start one attempt
+// of a task and hand the outcome back to `Shared::settle`.
+trait Executor<T>: std::marker::Send + Sync {
+    fn spawn(&self, shared: Arc<Shared<T>>, id: Uuid);
+
+    fn threads(&self) -> usize;
+}
+
+struct Pool(Arc<ThreadPool>);
+
+impl<T> Executor<T> for Pool
+where
+    T: Task + Ord + std::marker::Send + 'static,
+{
+    fn spawn(&self, shared: Arc<Shared<T>>, id: Uuid) {
+        self.0.spawn_fifo(move || attempt(shared, id));
+    }
+
+    fn threads(&self) -> usize {
+        self.0.current_num_threads()
+    }
+}
+
+// Rayon's global pool, built the first time a worker uses it and shared by
+// every worker that isn't given a pool of its own.
+struct Global;
+
+impl<T> Executor<T> for Global
+where
+    T: Task + Ord + std::marker::Send + 'static,
+{
+    fn spawn(&self, shared: Arc<Shared<T>>, id: Uuid) {
+        rayon::spawn_fifo(move || attempt(shared, id));
+    }
+
+    fn threads(&self) -> usize {
+        rayon::current_num_threads()
+    }
+}
+
+// Runs a spawned task on the thread the pool picked.
+fn attempt<T>(shared: Arc<Shared<T>>, id: Uuid)
+where
+    T: Task + Ord + std::marker::Send + 'static,
+{
+    let Some((mut entry, ctx)) = shared.begin(id, Task::timeout, Task::priority) else {
+        return;
+    };
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| entry.task.try_process_with(&ctx)));
+    let policy = entry.task.retry_policy();
+    shared.settle(entry, outcome, policy, ctx.token());
+}
+
+#[cfg(feature = "tokio")]
+struct Runtime(Handle);
+
+#[cfg(feature = "tokio")]
+impl<T> Executor<T> for Runtime
+where
+    T: AsyncTask + Ord + 'static,
+{
+    fn spawn(&self, shared: Arc<Shared<T>>, id: Uuid) {
+        self.0.spawn(async move {
+            let Some((mut entry, ctx)) = shared.begin(id, AsyncTask::timeout, AsyncTask::priority)
+            else {
+                return;
+            };
+            let outcome = {
+                let mut attempt = Box::pin(entry.task.try_process_with(&ctx));
+                let attempt = std::future::poll_fn(|cx| {
+                    match panic::catch_unwind(AssertUnwindSafe(|| attempt.as_mut().poll(cx))) {
+                        Ok(Poll::Pending) => Poll::Pending,
+                        Ok(Poll::Ready(result)) => Poll::Ready(Ok(result)),
+                        Err(payload) => Poll::Ready(Err(payload)),
+                    }
+                });
+
+                // Dropping the attempt at its deadline is what stops it.
+                match ctx.remaining() {
+                    Some(remaining) => match tokio::time::timeout(remaining, attempt).await {
+                        Ok(outcome) => outcome,
+                        Err(_) => Ok(Err(Failure::from("task timed out"))),
+                    },
+                    None => attempt.await,
+                }
+            };
+            let policy = entry.task.retry_policy();
+            shared.settle(entry, outcome, policy, ctx.token());
+        });
+    }
+
+    fn threads(&self) -> usize {
+        self.0.metrics().num_workers()
+    }
+}
 
 pub struct Worker<T> {
     pub id: Uuid,
     pub channel: String,
-    pub queue: Queue<T>,
-    pub active: bool,
+    shared: Arc<Shared<T>>,
 }
 
 impl<T> Default for Worker<T>
@@ -22,7 +715,7 @@ where
 
 impl<T> Worker<T>
 where
-    T: Task + Ord + std::marker::Send + 'static,
+    T: Ord + std::marker::Send + 'static,
 {
     fn generate_name() -> String {
         let mut generator = Generator::with_naming(Name::Numbered);
@@ -32,7 +725,17 @@ where
         }
     }
 
-    pub fn init(id: Option<Uuid>, channel: Option<String>, queue: Option<Queue<T>>) -> Self {
+    fn build<E>(
+        id: Option<Uuid>,
+        channel: Option<String>,
+        queue: Queue<Entry<T>>,
+        dead: Vec<DeadLetter<T>>,
+        backup: Option<Backup<T>>,
+        executor: E,
+    ) -> Self
+    where
+        E: FnOnce(&str) -> Arc<dyn Executor<T>>,
+    {
         let id = match id {
             Some(id) => id,
             None => Uuid::new_v4(),
@@ -43,19 +746,717 @@ where
             None => Self::generate_name(),
         };
 
-        let queue = match queue {
-            Some(queue) => queue,
-            None => Queue::new(),
+        let shared = Shared {
+            queue: Mutex::new(queue),
+            delayed: Mutex::new(BinaryHeap::new()),
+            wake: Condvar::new(),
+            timer: AtomicBool::new(false),
+            active: AtomicBool::new(false),
+            running: AtomicUsize::new(0),
+            concurrency: AtomicUsize::new(usize::MAX),
+            retry: RwLock::new(RetryPolicy::never()),
+            timeout: RwLock::new(None),
+            attempts: Mutex::new(HashMap::new()),
+            settled: Condvar::new(),
+            spawned: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            logger: RwLock::new(None),
+            state: RwLock::new(None),
+            outputs: RwLock::new(None),
+            worker_id: id,
+            channel: channel.clone(),
+            dead: Mutex::new(dead),
+            executor: RwLock::new(executor(&channel)),
+            backup,
         };
 
         Self {
             id,
-            queue,
             channel,
-            active: true,
+            shared: Arc::new(shared),
         }
     }
 
+    fn entries(queue: Option<Queue<T>>) -> Queue<Entry<T>> {
+        let queue = queue.unwrap_or_default();
+        let entries = queue.into_vec().into_iter().map(Entry::new).collect();
+
+        Queue::from(entries)
+    }
+
+    fn set_executor(&mut self, executor: Arc<dyn Executor<T>>) {
+        *self.shared.executor.write().unwrap() = executor;
+        self.shared.dispatch();
+    }
+
+    pub fn queue(&self) -> MutexGuard<'_, Queue<Entry<T>>> {
+        self.shared.queue.lock().unwrap()
+    }
+
+    /// The number of tasks waiting out a retry delay or a scheduled time
+    /// before they go in the queue.
+    pub fn delayed(&self) -> usize {
+        self.shared.delayed.lock().unwrap().len()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.shared.active.load(Ordering::SeqCst)
+    }
+
+    pub fn backup(&self) -> Option<&Backup<T>> {
+        self.shared.backup.as_ref()
+    }
+
+    /// The number of tasks currently being processed.
+    pub fn running(&self) -> usize {
+        self.shared.running.load(Ordering::SeqCst)
+    }
+
+    /// The most tasks this worker will process at once, or `None` if it
+    /// starts everything as soon as it's assigned.
+    pub fn concurrency(&self) -> Option<usize> {
+        match self.shared.concurrency.load(Ordering::SeqCst) {
+            usize::MAX => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Caps how many tasks run at once. Everything else waits in the queue,
+    /// so a higher-priority task assigned later still starts before the
+    /// lower-priority ones already waiting. A limit of zero is treated as one.
+    pub fn set_concurrency(&mut self, limit: Option<usize>) {
+        let limit = match limit {
+            Some(limit) => limit.max(1),
+            None => usize::MAX,
+        };
+        self.shared.concurrency.store(limit, Ordering::SeqCst);
+        self.shared.dispatch();
+    }
+
+    pub fn with_concurrency(mut self, limit: usize) -> Self {
+        self.set_concurrency(Some(limit));
+        self
+    }
+
+    /// Tasks that failed for good, oldest first.
+    pub fn dead_letters(&self) -> MutexGuard<'_, Vec<DeadLetter<T>>> {
+        self.shared.dead.lock().unwrap()
+    }
+
+    /// Puts a dead-lettered task back in the queue with a fresh set of
+    /// attempts. Returns `false` if there's no dead letter with that id.
+    pub fn requeue_dead_letter(&mut self, id: Uuid) -> Result<bool> {
+        let letter = {
+            let mut dead = self.shared.dead.lock().unwrap();
+            match dead.iter().position(|letter| letter.id == id) {
+                Some(index) => dead.remove(index),
+                None => return Ok(false),
+            }
+        };
+
+        self.shared.revive(vec![letter]).map(|_| true)
+    }
+
+    pub fn requeue_dead_letters(&mut self) -> Result<usize> {
+        let letters = std::mem::take(&mut *self.shared.dead.lock().unwrap());
+        self.shared.revive(letters)
+    }
+
+    pub fn purge_dead_letter(&mut self, id: Uuid) -> Result<bool> {
+        let mut dead = self.shared.dead.lock().unwrap();
+        let index = match dead.iter().position(|letter| letter.id == id) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+
+        if let Some((backup, record)) = self.shared.backup.as_ref().zip(dead[index].record.as_ref())
+        {
+            backup.forget(record)?;
+        }
+        dead.remove(index);
+
+        Ok(true)
+    }
+
+    pub fn purge_dead_letters(&mut self) -> Result<usize> {
+        let mut dead = self.shared.dead.lock().unwrap();
+        let mut purged = 0;
+        let forgotten = dead.iter().try_for_each(|letter| {
+            if let Some((backup, record)) = self.shared.backup.as_ref().zip(letter.record.as_ref())
+            {
+                backup.forget(record)?;
+            }
+            purged += 1;
+            Ok(())
+        });
+        dead.drain(..purged);
+
+        forgotten.map(|_| purged)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        *self.shared.retry.read().unwrap()
+    }
+
+    /// Sets how failed tasks are retried, unless a task brings its own
+    /// policy. By default nothing is retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        *self.shared.retry.write().unwrap() = policy;
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.set_retry_policy(policy);
+        self
+    }
+
+    /// How long an attempt can run before it's cancelled and counted as a
+    /// failure, unless a task brings its own timeout. `None`, the default,
+    /// lets tasks run for as long as they take.
+    pub fn timeout(&self) -> Option<Duration> {
+        *self.shared.timeout.read().unwrap()
+    }
+
+    /// Sets the timeout for attempts started from now on. Threads can't be
+    /// interrupted, so a [`Task`] past its deadline keeps its thread until it
+    /// notices the cancellation or finishes; either way the attempt fails,
+    /// and is retried per the retry policy.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        *self.shared.timeout.write().unwrap() = timeout;
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(Some(timeout));
+        self
+    }
+
+    /// Asks the running task with this id to stop. Its attempt fails and it
+    /// is dead-lettered without a retry. Returns `false` if no such task is
+    /// running.
+    pub fn cancel(&self, id: Uuid) -> bool {
+        match self.shared.attempts.lock().unwrap().get(&id) {
+            Some(ctx) => {
+                ctx.token().cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The number of running tasks past their deadline - stuck, or about to
+    /// notice they've been cancelled.
+    pub fn overdue(&self) -> usize {
+        let attempts = self.shared.attempts.lock().unwrap();
+        attempts
+            .values()
+            .filter(|ctx| ctx.token().is_expired())
+            .count()
+    }
+
+    /// How far along the running task with this id says it is, from 0.0 to
+    /// 1.0; see [`TaskContext::set_progress`].
+    pub fn progress(&self, id: Uuid) -> Option<f64> {
+        let attempts = self.shared.attempts.lock().unwrap();
+        attempts.get(&id).map(TaskContext::progress)
+    }
+
+    /// Sends what tasks log with [`TaskContext::log`] to `logger` instead of
+    /// stderr, from the next attempt on.
+    pub fn set_logger<F>(&mut self, logger: F)
+    where
+        F: Fn(&TaskContext, &str) + std::marker::Send + Sync + 'static,
+    {
+        *self.shared.logger.write().unwrap() = Some(Arc::new(logger));
+    }
+
+    pub fn with_logger<F>(mut self, logger: F) -> Self
+    where
+        F: Fn(&TaskContext, &str) + std::marker::Send + Sync + 'static,
+    {
+        self.set_logger(logger);
+        self
+    }
+
+    /// Shares `state` - a connection pool, say, or a cache - with every task
+    /// this worker runs from now on, through [`TaskContext::state`].
+    pub fn set_state<S>(&mut self, state: S)
+    where
+        S: Any + std::marker::Send + Sync,
+    {
+        *self.shared.state.write().unwrap() = Some(Arc::new(state));
+    }
+
+    pub fn with_state<S>(mut self, state: S) -> Self
+    where
+        S: Any + std::marker::Send + Sync,
+    {
+        self.set_state(state);
+        self
+    }
+
+    /// The number of threads this worker's tasks run on.
+    pub fn threads(&self) -> usize {
+        self.shared.executor.read().unwrap().threads()
+    }
+
+    pub fn clock_in(&mut self) {
+        self.shared.active.store(true, Ordering::SeqCst);
+        self.shared.dispatch();
+    }
+
+    pub fn clock_out(&mut self) {
+        self.shared.active.store(false, Ordering::SeqCst)
+    }
+
+    /// Stops starting tasks until [`Worker::resume`], without losing any.
+    /// Unlike [`Worker::clock_out`], tasks already handed to the thread pool
+    /// or runtime but not yet started go back in the queue, in priority
+    /// order, rather than run. Tasks that are running finish, and new ones
+    /// are still accepted.
+    pub fn pause(&mut self) {
+        self.shared.pause(true);
+    }
+
+    pub fn resume(&mut self) {
+        self.shared.pause(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stops the worker for good: it takes no more tasks, its listeners end,
+    /// and it waits up to `deadline` for the tasks already running. Those
+    /// still running then are cancelled, and everything that didn't start is
+    /// taken out of the worker; see [`ShutdownReport`] for where each ends
+    /// up.
+    pub fn shutdown(&mut self, deadline: Duration) -> ShutdownReport<T> {
+        self.shared.closed.store(true, Ordering::SeqCst);
+        self.clock_out();
+
+        let until = Instant::now() + deadline;
+        let mut attempts = self.shared.attempts.lock().unwrap();
+        let running = self.shared.running.load(Ordering::SeqCst);
+        while self.shared.running.load(Ordering::SeqCst) > 0 {
+            let now = Instant::now();
+            if now >= until {
+                break;
+            }
+            attempts = self
+                .shared
+                .settled
+                .wait_timeout(attempts, until - now)
+                .unwrap()
+                .0;
+        }
+
+        drop(attempts);
+
+        // Tasks still waiting on a thread never start, and are handed back
+        // with the rest of the queue. Holding on to them while the running
+        // ones are cancelled keeps any from starting in between.
+        let mut spawned = self.shared.spawned.lock().unwrap();
+        let attempts = self.shared.attempts.lock().unwrap();
+        let interrupted: Vec<Uuid> = attempts
+            .iter()
+            .map(|(id, ctx)| {
+                ctx.token().cancel();
+                *id
+            })
+            .collect();
+        drop(attempts);
+        let unstarted: Vec<Entry<T>> = spawned.drain().map(|(_, entry)| entry).collect();
+        drop(spawned);
+
+        let skipped = unstarted.len();
+        self.shared.running.fetch_sub(skipped, Ordering::SeqCst);
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.extend(unstarted);
+        let mut pending = queue.drain_sorted();
+        drop(queue);
+        let mut delayed =
+            std::mem::take(&mut *self.shared.delayed.lock().unwrap()).into_sorted_vec();
+        // `Due` sorts the earliest highest, so these come latest first.
+        delayed.reverse();
+        pending.extend(delayed.into_iter().map(|due| due.entry));
+
+        ShutdownReport {
+            finished: running.saturating_sub(interrupted.len() + skipped),
+            interrupted,
+            pending,
+        }
+    }
+
+    /// Whether [`Worker::shutdown`] has been called.
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::SeqCst)
+    }
+
+    /// Queues a task. While the worker is clocked in it is picked up as soon
+    /// as there's capacity; otherwise it waits for the next `clock_in`. A
+    /// persistent worker backs it up to Redis first, and doesn't queue it if
+    /// that fails, so it's safe to assign again on an error.
+    pub fn assign_one(&mut self, task: T) -> Result<()> {
+        self.shared.enqueue(vec![task])
+    }
+
+    pub fn assign_many<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<()> {
+        self.shared.enqueue(iter.into_iter().collect())
+    }
+
+    /// Holds a task back until `at`, then queues it by its priority like any
+    /// other. A time already past queues it straight away. A persistent
+    /// worker keeps it in Redis meanwhile, so it survives a restart.
+    pub fn assign_at(&mut self, task: T, at: SystemTime) -> Result<()> {
+        self.shared.schedule(task, at)
+    }
+
+    /// Holds a task back for `delay`; see [`Worker::assign_at`].
+    pub fn assign_after(&mut self, task: T, delay: Duration) -> Result<()> {
+        self.shared.schedule(task, SystemTime::now() + delay)
+    }
+
+    // Queues tasks for as long as the worker is around, for callers that
+    // can't hold on to a borrow of it.
+    pub(crate) fn sender(&self) -> impl Fn(T) -> Result<()> + std::marker::Send + 'static {
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        move |task| match shared.upgrade() {
+            Some(shared) => shared.enqueue(vec![task]),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns each message into a task with `decode` and queues it, for use as
+    /// a [`Router`](crate::router::Router) handler. Breaks once the worker is
+    /// dropped.
+    pub fn handler<F>(
+        &self,
+        mut decode: F,
+    ) -> impl FnMut(&Msg) -> ControlFlow<()> + std::marker::Send + 'static
+    where
+        F: FnMut(&Msg) -> Option<T> + std::marker::Send + 'static,
+    {
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        move |msg| {
+            let shared = match shared.upgrade() {
+                Some(shared) if !shared.closed.load(Ordering::SeqCst) => shared,
+                _ => return ControlFlow::Break(()),
+            };
+
+            if let Some(task) = decode(msg) {
+                shared.adopt(vec![Entry::new(task)]);
+            }
+
+            ControlFlow::Continue
+        }
+    }
+
+    /// Pauses or resumes the worker on each [`Command`] it's sent, for use
+    /// as a [`Router`](crate::router::Router) handler. Messages that aren't
+    /// commands are ignored. Breaks once the worker is dropped.
+    pub fn control_handler(
+        &self,
+    ) -> impl FnMut(&Msg) -> ControlFlow<()> + std::marker::Send + 'static {
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        move |msg| {
+            let shared = match shared.upgrade() {
+                Some(shared) => shared,
+                None => return ControlFlow::Break(()),
+            };
+
+            let command = std::str::from_utf8(msg.get_payload_bytes())
+                .ok()
+                .and_then(|command| command.parse().ok());
+            match command {
+                Some(Command::Pause) => shared.pause(true),
+                Some(Command::Resume) => shared.pause(false),
+                None => {}
+            }
+
+            ControlFlow::Continue
+        }
+    }
+
+    /// Listens for [`Command`]s sent to this worker's channel with
+    /// [`Client::control`], so it can be paused and resumed from another
+    /// process. See [`Worker::control_handler`].
+    #[cfg(not(tarpaulin_include))]
+    pub fn control(&self, client: &Client) -> Result<Listener> {
+        let mut handler = self.control_handler();
+        client.listen(Command::generate_key(&self.channel), move |msg| {
+            handler(&msg)
+        })
+    }
+
+    /// Reads tasks from this worker's channel's stream as a member of
+    /// `group`, turning each one into a task with `decode`. A task is only
+    /// acknowledged once it's processed successfully or dead-lettered, so
+    /// one that was in flight when its worker died is claimed by another
+    /// member after the group's `min_idle`. Entries `decode` rejects are
+    /// acknowledged and dropped.
+    ///
+    /// The worker reads no more than `group.batch` tasks ahead, leaving the
+    /// rest in the stream for other members.
+    #[cfg(not(tarpaulin_include))]
+    pub fn consume_with<F>(
+        &mut self,
+        client: &Client,
+        group: ConsumerGroup,
+        mut decode: F,
+    ) -> Result<Listener>
+    where
+        F: FnMut(&[u8]) -> Option<T> + std::marker::Send + 'static,
+    {
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        let batch = group.batch;
+        streams::consume(
+            client,
+            &self.channel,
+            group,
+            self.id.to_string(),
+            move |messages| {
+                let shared = match shared.upgrade() {
+                    Some(shared) if !shared.closed.load(Ordering::SeqCst) => shared,
+                    _ => return std::ops::ControlFlow::Break(()),
+                };
+
+                let entries: Vec<Entry<T>> = messages
+                    .into_iter()
+                    .filter_map(|message| match decode(&message.payload) {
+                        Some(task) => {
+                            let mut entry = Entry::new(task);
+                            let delivery = message.delivery;
+                            entry.receipt = Some(Receipt::new(move || delivery.ack()));
+                            Some(entry)
+                        }
+                        None => {
+                            let _ = message.delivery.ack();
+                            None
+                        }
+                    })
+                    .collect();
+                if !entries.is_empty() {
+                    shared.requeue(entries);
+                }
+
+                let waiting = shared.queue.lock().unwrap().len();
+                std::ops::ControlFlow::Continue(batch.saturating_sub(waiting))
+            },
+        )
+    }
+
+    /// Feeds this worker from a queue shared with other processes, turning
+    /// each task into one of this worker's with `decode`. The worker holds
+    /// at most `batch` tasks of its own, so the rest stay in the shared
+    /// queue for everyone else. A task popped here is this worker's alone;
+    /// use a persistent worker to keep it safe from a crash.
+    #[cfg(not(tarpaulin_include))]
+    pub fn pull_with<U, F>(
+        &mut self,
+        queue: DistributedQueue<U>,
+        batch: usize,
+        mut decode: F,
+    ) -> Listener
+    where
+        U: Priority + Serialize + DeserializeOwned + std::marker::Send + 'static,
+        F: FnMut(U) -> Option<T> + std::marker::Send + 'static,
+    {
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        let batch = batch.max(1);
+        queue.pull(move |task| {
+            let shared = match shared.upgrade() {
+                Some(shared) => shared,
+                None => return std::ops::ControlFlow::Break(()),
+            };
+
+            // Already popped from the shared queue, so it's kept even after
+            // a shutdown rather than lost.
+            if let Some(task) = task.and_then(&mut decode) {
+                shared.adopt(vec![Entry::new(task)]);
+            }
+            if shared.closed.load(Ordering::SeqCst) {
+                return std::ops::ControlFlow::Break(());
+            }
+
+            let waiting = shared.queue.lock().unwrap().len();
+            std::ops::ControlFlow::Continue(batch.saturating_sub(waiting))
+        })
+    }
+
+    /// Consumes this worker's channel from `broker`, turning each message
+    /// into a task with `decode`. A message is acknowledged once its task is
+    /// processed successfully or dead-lettered, and ones `decode` rejects are
+    /// acknowledged and dropped. Retries happen in the worker, so whether a
+    /// task in flight survives a crash is up to the broker.
+    ///
+    /// The worker holds at most `batch` tasks of its own, leaving the rest
+    /// with the broker for other workers.
+    pub fn consume_from_with<F>(
+        &mut self,
+        broker: Arc<dyn Broker>,
+        batch: usize,
+        mut decode: F,
+    ) -> Listener
+    where
+        F: FnMut(&[u8]) -> Option<T> + std::marker::Send + 'static,
+    {
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        let batch = batch.max(1);
+        let acker = Arc::clone(&broker);
+        broker::pull(broker, self.channel.clone(), move |message| {
+            let shared = match shared.upgrade() {
+                Some(shared) if !shared.closed.load(Ordering::SeqCst) => shared,
+                _ => return std::ops::ControlFlow::Break(()),
+            };
+
+            if let Some(message) = message {
+                match decode(&message.payload) {
+                    Some(task) => {
+                        let acker = Arc::clone(&acker);
+                        let mut entry = Entry::new(task);
+                        entry.receipt = Some(Receipt::new(move || acker.ack(&message)));
+                        shared.requeue(vec![entry]);
+                    }
+                    None => {
+                        let _ = acker.ack(&message);
+                    }
+                }
+            }
+
+            let waiting = shared.queue.lock().unwrap().len();
+            std::ops::ControlFlow::Continue(batch.saturating_sub(waiting))
+        })
+    }
+
+    /// Subscribes to this worker's channel, turning each message into a task
+    /// with `decode` and queueing it. Tasks are processed as they arrive while
+    /// the worker is clocked in, and held in the queue otherwise. The
+    /// subscription ends once the worker is dropped or the returned
+    /// [`Listener`] is stopped.
+    #[cfg(not(tarpaulin_include))]
+    pub fn listen_with<F>(&mut self, client: &Client, decode: F) -> Result<Listener>
+    where
+        F: FnMut(&Msg) -> Option<T> + std::marker::Send + 'static,
+    {
+        let mut handler = self.handler(decode);
+        client.listen(self.channel.clone(), move |msg| handler(&msg))
+    }
+}
+
+impl<T> Worker<T>
+where
+    T: Produce + Ord + std::marker::Send + 'static,
+{
+    /// Queues a task like [`Worker::assign_one`], returning a handle to wait
+    /// on for its output. A task with a [`Produce::result_id`] goes by that
+    /// id rather than a fresh one.
+    pub fn submit(&mut self, task: T) -> Result<output::Handle<T::Output>> {
+        self.shared.accepting()?;
+        self.shared
+            .outputs
+            .write()
+            .unwrap()
+            .get_or_insert_with(Outputs::new);
+
+        // The entry keeps an id of its own, as tasks can share a result id.
+        let mut entry = Entry::new(task);
+        let id = entry.task.result_id().unwrap_or(entry.id);
+        let (handle, reply) = output::Handle::new(id);
+        entry.reply = Some(reply);
+        self.shared.push(vec![entry])?;
+
+        Ok(handle)
+    }
+
+    // Queues tasks and calls back with how each one went, for callers that
+    // can't hold on to a borrow of the worker. Tasks queued without a
+    // callback don't need to produce an output.
+    pub(crate) fn submitter(
+        &self,
+    ) -> impl Fn(T, Option<Callback<T::Output>>) -> Result<()> + std::marker::Send + Sync + 'static
+    {
+        self.shared
+            .outputs
+            .write()
+            .unwrap()
+            .get_or_insert_with(Outputs::new);
+
+        let shared: Weak<Shared<T>> = Arc::downgrade(&self.shared);
+        move |task, callback| {
+            let mut entry = Entry::new(task);
+            entry.reply = callback.map(Reply::typed);
+
+            // Dropped with the entry if the worker is gone, which says so.
+            match shared.upgrade() {
+                Some(shared) => {
+                    shared.accepting()?;
+                    shared.push(vec![entry])
+                }
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Stores the output of every task this worker finishes in `backend`,
+    /// and why it failed for the ones that are dead-lettered, however they
+    /// were queued.
+    pub fn set_results(&mut self, backend: ResultBackend)
+    where
+        T::Output: Serialize,
+    {
+        let mut outputs = Outputs::new();
+        outputs.results = Some((backend, Outputs::<T>::store as Store));
+        *self.shared.outputs.write().unwrap() = Some(outputs);
+    }
+
+    pub fn with_results(mut self, backend: ResultBackend) -> Self
+    where
+        T::Output: Serialize,
+    {
+        self.set_results(backend);
+        self
+    }
+}
+
+impl<T> Worker<T>
+where
+    T: Task + Ord + std::marker::Send + 'static,
+{
+    /// Builds a pool whose threads are named after `channel`, so they're easy
+    /// to pick out in a debugger or `top -H`. Zero threads means one per CPU.
+    pub fn build_pool(channel: &str, threads: usize) -> Result<ThreadPool> {
+        let prefix = channel.to_string();
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(move |index| format!("{prefix}-{index}"))
+            .build()?;
+
+        Ok(pool)
+    }
+
+    fn pooled(_channel: &str) -> Arc<dyn Executor<T>> {
+        Arc::new(Global)
+    }
+
+    /// A worker that runs its tasks on rayon's global pool, shared with
+    /// every other worker that isn't given a pool of its own, so starting
+    /// one never spawns threads.
+    pub fn init(id: Option<Uuid>, channel: Option<String>, queue: Option<Queue<T>>) -> Self {
+        let queue = Self::entries(queue);
+
+        Self::build(id, channel, queue, Vec::new(), None, Self::pooled)
+    }
+
+    /// A worker with a pool of its own, one thread per CPU named after its
+    /// channel, or an error if the threads can't be started.
+    pub fn try_init(
+        id: Option<Uuid>,
+        channel: Option<String>,
+        queue: Option<Queue<T>>,
+    ) -> Result<Self> {
+        Self::init(id, channel, queue).with_threads(0)
+    }
+
     pub fn new() -> Self {
         Self::init(None, None, None)
     }
@@ -66,23 +1467,176 @@ where
         Self::init(None, None, Some(queue))
     }
 
-    pub fn clock_in(&mut self) {
-        self.active = true;
+    /// Runs this worker's tasks on `pool` from now on. Tasks already running
+    /// finish where they started. Handing the same pool to several workers
+    /// lets them share threads while keeping them apart from everyone else.
+    pub fn set_pool(&mut self, pool: Arc<ThreadPool>) {
+        self.set_executor(Arc::new(Pool(pool)));
+    }
+
+    pub fn with_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.set_pool(pool);
+        self
+    }
+
+    /// Gives this worker a pool of its own with `threads` threads, named
+    /// after its channel. Workers otherwise share rayon's global pool.
+    pub fn with_threads(mut self, threads: usize) -> Result<Self> {
+        let pool = Self::build_pool(&self.channel, threads)?;
+        self.set_pool(Arc::new(pool));
+
+        Ok(self)
+    }
+}
+
+/// Workers for [`AsyncTask`]s, which run as tokio tasks rather than on a
+/// thread pool. Everything else - ordering, concurrency limits, retries and
+/// the `clock_in` / `clock_out` lifecycle - works the same.
+#[cfg(feature = "tokio")]
+impl<T> Worker<T>
+where
+    T: AsyncTask + Ord + 'static,
+{
+    pub fn init_async(
+        id: Option<Uuid>,
+        channel: Option<String>,
+        queue: Option<Queue<T>>,
+        runtime: Handle,
+    ) -> Self {
+        let queue = Self::entries(queue);
+
+        Self::build(id, channel, queue, Vec::new(), None, |_| {
+            Arc::new(Runtime(runtime))
+        })
+    }
+
+    /// Creates a worker on the tokio runtime it's called from.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside of a tokio runtime.
+    pub fn new_async() -> Self {
+        Self::init_async(None, None, None, Handle::current())
+    }
+
+    pub fn from_async(vec: Vec<T>) -> Self {
+        let queue = Queue::from(vec);
+
+        Self::init_async(None, None, Some(queue), Handle::current())
+    }
+
+    /// Runs this worker's tasks on `runtime` from now on. Tasks already
+    /// running finish where they started.
+    pub fn set_runtime(&mut self, runtime: Handle) {
+        self.set_executor(Arc::new(Runtime(runtime)));
+    }
+
+    pub fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.set_runtime(runtime);
+        self
+    }
+}
 
-        for mut item in self.queue.drain_sorted() {
-            rayon::spawn(move || item.process());
+#[cfg(not(tarpaulin_include))]
+impl<T> Worker<T>
+where
+    T: Ord + Serialize + DeserializeOwned + std::marker::Send + 'static,
+{
+    fn restore<E>(id: Option<Uuid>, channel: String, client: Client, executor: E) -> Result<Self>
+    where
+        E: FnOnce(&str) -> Arc<dyn Executor<T>>,
+    {
+        let backup = Backup::new(client, &channel);
+        let entries = backup
+            .load_raw()?
+            .into_iter()
+            .map(|(raw, task)| {
+                let mut entry = Entry::new(task);
+                entry.raw = Some(raw);
+                entry
+            })
+            .collect();
+        let dead = backup.load_dead()?;
+        let delayed = backup.load_delayed()?;
+
+        let worker = Self::build(
+            id,
+            Some(channel),
+            Queue::from(entries),
+            dead,
+            Some(backup),
+            executor,
+        );
+        for due in delayed {
+            let delay = due.at.duration_since(SystemTime::now()).unwrap_or_default();
+            let mut entry = Entry::new(due.task);
+            entry.raw = Some(due.raw);
+            worker.shared.hold(entry, delay, Some(due.member));
         }
+
+        Ok(worker)
     }
+}
 
-    pub fn clock_out(&mut self) {
-        self.active = false
+#[cfg(not(tarpaulin_include))]
+impl<T> Worker<T>
+where
+    T: Task + Ord + Serialize + DeserializeOwned + std::marker::Send + 'static,
+{
+    /// Creates a worker whose queue is mirrored to Redis under its channel,
+    /// rehydrating any tasks a previous worker on that channel left behind.
+    pub fn persistent(id: Option<Uuid>, channel: String, client: Client) -> Result<Self> {
+        Self::restore(id, channel, client, Self::pooled)
+    }
+}
+
+#[cfg(all(feature = "tokio", not(tarpaulin_include)))]
+impl<T> Worker<T>
+where
+    T: AsyncTask + Ord + Serialize + DeserializeOwned + 'static,
+{
+    /// The [`AsyncTask`] counterpart to [`Worker::persistent`].
+    pub fn persistent_async(
+        id: Option<Uuid>,
+        channel: String,
+        client: Client,
+        runtime: Handle,
+    ) -> Result<Self> {
+        Self::restore(id, channel, client, |_| Arc::new(Runtime(runtime)))
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+impl<T> Worker<T>
+where
+    T: Ord + DeserializeOwned + std::marker::Send + 'static,
+{
+    /// Subscribes to this worker's channel, expecting each message to be a
+    /// JSON-encoded task. See [`Worker::listen_with`].
+    pub fn listen(&mut self, client: &Client) -> Result<Listener> {
+        self.listen_with(client, |msg| {
+            serde_json::from_slice(msg.get_payload_bytes()).ok()
+        })
+    }
+
+    /// Feeds this worker from a shared queue of its own task type. See
+    /// [`Worker::pull_with`].
+    pub fn pull(&mut self, queue: DistributedQueue<T>, batch: usize) -> Listener
+    where
+        T: Priority + Serialize,
+    {
+        self.pull_with(queue, batch, Some)
     }
 
-    pub fn assign_one(&mut self, task: T) {
-        self.queue.push(task);
+    /// Consumes JSON-encoded tasks sent with [`Client::send`]. See
+    /// [`Worker::consume_with`].
+    pub fn consume(&mut self, client: &Client, group: ConsumerGroup) -> Result<Listener> {
+        self.consume_with(client, group, |raw| serde_json::from_slice(raw).ok())
     }
 
-    pub fn assign_many<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        self.queue.extend(iter);
+    /// Consumes JSON-encoded tasks from `broker`. See
+    /// [`Worker::consume_from_with`].
+    pub fn consume_from(&mut self, broker: Arc<dyn Broker>, batch: usize) -> Listener {
+        self.consume_from_with(broker, batch, |raw| serde_json::from_slice(raw).ok())
     }
 }
@@ -0,0 +1,337 @@
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use crate::entry::Reply;
+use crate::error::{Error, Result};
+use crate::output::{Handle, Produce};
+use crate::worker::{Callback, Worker};
+
+/// A step of a [`Workflow`], for later steps to run after.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Step {
+    workflow: Uuid,
+    index: usize,
+}
+
+impl Step {
+    /// Where the step comes in the order it was added to its workflow,
+    /// starting from zero. Failures name steps by this.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+struct Node<T> {
+    task: T,
+    after: Vec<usize>,
+    compensation: Option<T>,
+}
+
+/// Tasks that run after one another, and the outputs they pass along. Each
+/// step is queued on the worker once every step it runs after has
+/// succeeded, with their outputs handed to it through
+/// [`Produce::receive`]. Steps that don't wait on each other run side by
+/// side, each at its own priority.
+///
+/// If a step fails for good, nothing after it is queued, and the
+/// compensations of the steps that already succeeded are queued in reverse.
+/// The workflow is held in memory, so it doesn't outlive its process.
+pub struct Workflow<T> {
+    id: Uuid,
+    nodes: Vec<Node<T>>,
+    stray: bool,
+}
+
+impl<T> Default for Workflow<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Workflow<T> {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            nodes: Vec::new(),
+            stray: false,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds a step that can run straight away.
+    pub fn add(&mut self, task: T) -> Step {
+        self.add_after(&[], task)
+    }
+
+    /// Adds a step that runs once all of `after` have succeeded.
+    pub fn add_after(&mut self, after: &[Step], task: T) -> Step {
+        let mut upstream = Vec::with_capacity(after.len());
+        for step in after {
+            match step.workflow == self.id {
+                true => upstream.push(step.index),
+                false => self.stray = true,
+            }
+        }
+
+        self.nodes.push(Node {
+            task,
+            after: upstream,
+            compensation: None,
+        });
+
+        Step {
+            workflow: self.id,
+            index: self.nodes.len() - 1,
+        }
+    }
+
+    /// Adds steps that each run after the one before, the first of them
+    /// after all of `after`.
+    pub fn chain<I>(&mut self, after: &[Step], tasks: I) -> Vec<Step>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut steps: Vec<Step> = Vec::new();
+        for task in tasks {
+            let step = match steps.last() {
+                Some(previous) => self.add_after(&[*previous], task),
+                None => self.add_after(after, task),
+            };
+            steps.push(step);
+        }
+
+        steps
+    }
+
+    /// Adds steps that all run side by side once `after` have succeeded.
+    pub fn group<I>(&mut self, after: &[Step], tasks: I) -> Vec<Step>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        tasks
+            .into_iter()
+            .map(|task| self.add_after(after, task))
+            .collect()
+    }
+
+    /// Adds a group, then a `callback` step that runs once the whole group
+    /// has succeeded and receives all of its outputs.
+    pub fn chord<I>(&mut self, after: &[Step], tasks: I, callback: T) -> Step
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let group = self.group(after, tasks);
+        self.add_after(&group, callback)
+    }
+
+    /// Queues `task` if the workflow fails after `step` has succeeded, to
+    /// undo what it did.
+    pub fn compensate(&mut self, step: Step, task: T) {
+        match (step.workflow == self.id).then(|| self.nodes.get_mut(step.index)) {
+            Some(Some(node)) => node.compensation = Some(task),
+            _ => self.stray = true,
+        }
+    }
+}
+
+impl<T> Workflow<T>
+where
+    T: Produce + Ord + Send + 'static,
+    T::Output: Clone,
+{
+    /// Queues the steps that can run straight away on `worker`, and the rest
+    /// as they become ready. The handle gives the outputs of the steps
+    /// nothing runs after, in the order they were added, or the first
+    /// failure once every step that was running has settled.
+    pub fn run(self, worker: &Worker<T>) -> Result<Handle<Vec<T::Output>>> {
+        if self.stray {
+            return Err(Error::Workflow(String::from(
+                "it refers to a step of another workflow",
+            )));
+        }
+
+        let (handle, reply) = Handle::new(self.id);
+        let mut dependents = vec![Vec::new(); self.nodes.len()];
+        let mut waiting = Vec::with_capacity(self.nodes.len());
+        let mut tasks = Vec::with_capacity(self.nodes.len());
+        let mut after = Vec::with_capacity(self.nodes.len());
+        let mut compensations = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.into_iter().enumerate() {
+            for upstream in &node.after {
+                dependents[*upstream].push(index);
+            }
+            waiting.push(node.after.len());
+            tasks.push(Some(node.task));
+            after.push(node.after);
+            compensations.push(node.compensation);
+        }
+
+        let ready: Vec<(usize, T)> = waiting
+            .iter()
+            .enumerate()
+            .filter(|(_, waiting)| **waiting == 0)
+            .filter_map(|(index, _)| Some((index, tasks[index].take()?)))
+            .collect();
+
+        let run = Run {
+            outputs: (0..tasks.len()).map(|_| None).collect(),
+            tasks,
+            after,
+            dependents,
+            waiting,
+            compensations,
+            succeeded: Vec::new(),
+            running: ready.len(),
+            failure: None,
+            reply: Some(reply),
+        };
+        let flow = Arc::new(Flow {
+            run: Mutex::new(run),
+            submit: Box::new(worker.submitter()),
+        });
+
+        match ready.is_empty() {
+            true => flow.conclude(),
+            false => flow.start(ready)?,
+        }
+
+        Ok(handle)
+    }
+}
+
+// Where a running workflow is up to. Indexed by step.
+struct Run<T: Produce> {
+    tasks: Vec<Option<T>>,
+    after: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+    waiting: Vec<usize>,
+    outputs: Vec<Option<T::Output>>,
+    compensations: Vec<Option<T>>,
+    // In the order they finished, so they can be undone in reverse.
+    succeeded: Vec<usize>,
+    running: usize,
+    failure: Option<String>,
+    reply: Option<Reply>,
+}
+
+type Submit<T, O> = Box<dyn Fn(T, Option<Callback<O>>) -> Result<()> + Send + Sync>;
+
+struct Flow<T: Produce> {
+    run: Mutex<Run<T>>,
+    submit: Submit<T, T::Output>,
+}
+
+impl<T> Flow<T>
+where
+    T: Produce + Send + 'static,
+    T::Output: Clone,
+{
+    fn start(self: &Arc<Self>, ready: Vec<(usize, T)>) -> Result<()> {
+        let mut queued = Ok(());
+        for (index, task) in ready {
+            let flow = Arc::clone(self);
+            let callback = Box::new(move |outcome| flow.settle(index, outcome));
+            if let Err(err) = (self.submit)(task, Some(callback)) {
+                queued = Err(err);
+            }
+        }
+
+        queued
+    }
+
+    fn settle(self: &Arc<Self>, index: usize, outcome: std::result::Result<T::Output, String>) {
+        let mut ready = Vec::new();
+        let mut undo = Vec::new();
+        let mut run = self.run.lock().unwrap();
+        run.running -= 1;
+
+        match (outcome, run.failure.is_some()) {
+            // Finished after another step failed, so it's undone straight
+            // away rather than let anything after it run.
+            (Ok(_), true) => undo.extend(run.compensations[index].take()),
+            (Ok(output), false) => {
+                run.outputs[index] = Some(output);
+                run.succeeded.push(index);
+
+                for next in run.dependents[index].clone() {
+                    run.waiting[next] -= 1;
+                    if run.waiting[next] > 0 {
+                        continue;
+                    }
+
+                    let upstream = run.after[next]
+                        .iter()
+                        .filter_map(|upstream| run.outputs[*upstream].clone())
+                        .collect();
+                    if let Some(mut task) = run.tasks[next].take() {
+                        task.receive(upstream);
+                        ready.push((next, task));
+                    }
+                }
+                run.running += ready.len();
+            }
+            (Err(reason), false) => {
+                run.failure = Some(format!("step {index} failed: {reason}"));
+                let succeeded = mem::take(&mut run.succeeded);
+                undo.extend(
+                    succeeded
+                        .into_iter()
+                        .rev()
+                        .filter_map(|step| run.compensations[step].take()),
+                );
+            }
+            (Err(_), true) => {}
+        }
+
+        let finished = run.running == 0;
+        drop(run);
+
+        let _ = self.start(ready);
+        for task in undo {
+            // Nothing waits on a compensation, so it needn't produce
+            // anything.
+            let _ = (self.submit)(task, None);
+        }
+        if finished {
+            self.conclude();
+        }
+    }
+
+    // Answers the workflow's handle once nothing is left running.
+    fn conclude(&self) {
+        let mut run = self.run.lock().unwrap();
+        let Some(reply) = run.reply.take() else {
+            return;
+        };
+
+        let outcome = match run.failure.take() {
+            Some(failure) => Err(failure),
+            None => {
+                let leaves: Vec<usize> = (0..run.outputs.len())
+                    .filter(|index| run.dependents[*index].is_empty())
+                    .collect();
+                let outputs: Vec<T::Output> = leaves
+                    .into_iter()
+                    .filter_map(|index| run.outputs[index].take())
+                    .collect();
+                Ok(Box::new(outputs) as Box<dyn std::any::Any + Send>)
+            }
+        };
+        drop(run);
+
+        reply.send(outcome);
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+    use serde::{Deserialize, Serialize};
+    use toretsu::async_client::AsyncClient;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Email {
+        to: String,
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn async_client_pubsub() {
+        let client = AsyncClient::try_new().await.unwrap();
+        let mut subscription = client.subscribe("async-test").await.unwrap();
+
+        client.publish("async-test", "Hello World!").await.unwrap();
+        let msg = tokio::time::timeout(Duration::from_secs(3), subscription.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(msg.get_channel_name(), "async-test");
+        assert_eq!(msg.get_payload::<String>().unwrap(), "Hello World!");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn async_client_decode() {
+        let client = AsyncClient::try_new().await.unwrap();
+        let mut emails = client
+            .subscribe("async-decode")
+            .await
+            .unwrap()
+            .decode::<Email>();
+
+        let email = Email {
+            to: String::from("ops@example.com"),
+        };
+        let payload = serde_json::to_string(&email).unwrap();
+        client
+            .clone()
+            .publish("async-decode", payload)
+            .await
+            .unwrap();
+
+        let decoded = tokio::time::timeout(Duration::from_secs(3), emails.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.unwrap(), email);
+    }
+}
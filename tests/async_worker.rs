@@ -0,0 +1,158 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use toretsu::retry::RetryPolicy;
+    use toretsu::task::{AsyncTask, Failure};
+    use toretsu::worker::Worker;
+
+    static STARTED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+    static FINISHED: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    struct Fetch {
+        priority: u32,
+        wait: Duration,
+    }
+
+    impl Fetch {
+        fn new(priority: u32, millis: u64) -> Self {
+            Self {
+                priority,
+                wait: Duration::from_millis(millis),
+            }
+        }
+    }
+
+    impl AsyncTask for Fetch {
+        async fn process(&mut self) {
+            STARTED.lock().unwrap().push(self.priority);
+            tokio::time::sleep(self.wait).await;
+            FINISHED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_async_worker() {
+        let mut worker =
+            Worker::from_async(vec![Fetch::new(1, 20), Fetch::new(3, 100)]).with_concurrency(1);
+        worker.assign_one(Fetch::new(2, 20)).unwrap();
+        assert!(!worker.is_active());
+        assert_eq!(worker.queue().len(), 3);
+
+        worker.clock_in();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        worker.assign_one(Fetch::new(4, 20)).unwrap();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        assert!(worker.queue().is_empty());
+        assert_eq!(*STARTED.lock().unwrap(), vec![3, 4, 2, 1]);
+
+        worker.clock_out();
+        worker.set_concurrency(None);
+        let before = FINISHED.load(Ordering::SeqCst);
+        worker
+            .assign_many((0..1000).map(|priority| Fetch::new(priority, 200)))
+            .unwrap();
+        assert_eq!(worker.queue().len(), 1000);
+
+        let start = Instant::now();
+        worker.clock_in();
+        while FINISHED.load(Ordering::SeqCst) < before + 1000 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(worker.threads(), 2);
+    }
+
+    static ATTEMPTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    enum Upload {
+        Flaky(u32),
+        Panicky,
+    }
+
+    impl AsyncTask for Upload {
+        async fn process(&mut self) {
+            let _ = self.try_process().await;
+        }
+
+        async fn try_process(&mut self) -> Result<(), Failure> {
+            tokio::task::yield_now().await;
+            match self {
+                Upload::Flaky(remaining) => {
+                    ATTEMPTS.lock().unwrap().push("flaky");
+                    if *remaining == 0 {
+                        return Ok(());
+                    }
+                    *remaining -= 1;
+                    Err("connection reset".into())
+                }
+                Upload::Panicky => {
+                    ATTEMPTS.lock().unwrap().push("panicky");
+                    panic!("unexpected response")
+                }
+            }
+        }
+
+        fn retry_policy(&self) -> Option<RetryPolicy> {
+            match self {
+                Upload::Flaky(_) => None,
+                Upload::Panicky => Some(RetryPolicy::never()),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_async_worker_retry() {
+        let policy = RetryPolicy::new(5)
+            .with_base_delay(Duration::from_millis(10))
+            .with_jitter(false);
+        let mut worker = Worker::new_async().with_retry_policy(policy);
+        worker.clock_in();
+        worker
+            .assign_many([Upload::Flaky(2), Upload::Panicky])
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let attempts = ATTEMPTS.lock().unwrap();
+        assert_eq!(attempts.iter().filter(|name| **name == "flaky").count(), 3);
+        assert_eq!(
+            attempts.iter().filter(|name| **name == "panicky").count(),
+            1
+        );
+
+        let dead = worker.dead_letters();
+        assert_eq!(dead.len(), 1);
+        assert!(dead[0].error.contains("unexpected response"));
+    }
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    struct Hang;
+
+    impl AsyncTask for Hang {
+        async fn process(&mut self) {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_millis(50))
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_async_worker_timeout() {
+        let mut worker = Worker::new_async();
+        worker.assign_one(Hang).unwrap();
+        worker.clock_in();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(worker.running(), 0);
+        let dead = worker.dead_letters();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].error, "task timed out after 50ms");
+    }
+}
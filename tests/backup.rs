@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+    use redis::Commands;
+    use serde::{Deserialize, Serialize};
+    use toretsu::backup::Backup;
+    use toretsu::client::Client;
+    use toretsu::task::{Failure, Task};
+    use toretsu::worker::Worker;
+
+    #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+    struct Job {
+        priority: i32,
+    }
+
+    impl Task for Job {
+        fn process(&mut self) {
+            println!("Processed item {:?}", self.priority)
+        }
+
+        fn try_process(&mut self) -> Result<(), Failure> {
+            if self.priority < 0 {
+                return Err(String::from("negative priority").into());
+            }
+
+            self.process();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn generate_key() {
+        let key = Backup::<Job>::generate_key("test");
+        assert_eq!(key, "toretsu:test:queue");
+
+        let key = Backup::<Job>::generate_delayed_key("test");
+        assert_eq!(key, "toretsu:test:delayed");
+    }
+
+    #[test]
+    #[ignore]
+    fn backup_roundtrip() {
+        let backup: Backup<Job> = Backup::new(Client::new(), "backup-roundtrip");
+        backup.clear().unwrap();
+
+        backup.push(&Job { priority: 3 }).unwrap();
+        backup
+            .push_many(&[Job { priority: 1 }, Job { priority: 2 }])
+            .unwrap();
+
+        let loaded = backup.load().unwrap();
+        assert_eq!(loaded.len(), 3);
+
+        let raw = backup.encode(&Job { priority: 1 }).unwrap();
+        backup.remove(&raw).unwrap();
+        assert_eq!(backup.load().unwrap().len(), 2);
+
+        backup.clear().unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_resumes() {
+        let channel = String::from("worker-resumes");
+        Backup::<Job>::new(Client::new(), &channel).clear().unwrap();
+
+        let mut worker: Worker<Job> =
+            Worker::persistent(None, channel.clone(), Client::new()).unwrap();
+        assert!(worker.queue().is_empty());
+
+        worker.assign_one(Job { priority: 7 }).unwrap();
+        worker
+            .assign_many(vec![Job { priority: 4 }, Job { priority: 9 }])
+            .unwrap();
+        drop(worker);
+
+        let mut restored: Worker<Job> = Worker::persistent(None, channel, Client::new()).unwrap();
+        assert_eq!(restored.queue().len(), 3);
+        assert_eq!(
+            restored.queue().peek().map(|entry| &entry.task),
+            Some(&Job { priority: 9 })
+        );
+
+        restored.clock_in();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let backup = restored.backup().unwrap();
+        assert!(backup.load().unwrap().is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_dead_letters_persist() {
+        let channel = String::from("worker-dead-letters");
+        Backup::<Job>::new(Client::new(), &channel).clear().unwrap();
+
+        let mut worker: Worker<Job> =
+            Worker::persistent(None, channel.clone(), Client::new()).unwrap();
+        worker.clock_in();
+        worker.assign_one(Job { priority: -1 }).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        assert_eq!(worker.dead_letters().len(), 1);
+        drop(worker);
+
+        let mut restored: Worker<Job> =
+            Worker::persistent(None, channel.clone(), Client::new()).unwrap();
+        assert!(restored.queue().is_empty());
+        let id = {
+            let dead = restored.dead_letters();
+            assert_eq!(dead.len(), 1);
+            assert_eq!(dead[0].error, "negative priority");
+            assert_eq!(dead[0].task, Job { priority: -1 });
+            dead[0].id
+        };
+
+        assert!(restored.requeue_dead_letter(id).unwrap());
+        assert_eq!(restored.backup().unwrap().load().unwrap().len(), 1);
+        assert!(restored.backup().unwrap().load_dead().unwrap().is_empty());
+
+        restored.backup().unwrap().clear().unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_scheduled_persist() {
+        let channel = String::from("worker-scheduled");
+        Backup::<Job>::new(Client::new(), &channel).clear().unwrap();
+
+        let mut worker: Worker<Job> =
+            Worker::persistent(None, channel.clone(), Client::new()).unwrap();
+        let delay = std::time::Duration::from_millis(300);
+        worker.assign_after(Job { priority: 1 }, delay).unwrap();
+        worker.assign_after(Job { priority: 1 }, delay).unwrap();
+        drop(worker);
+
+        let mut restored: Worker<Job> = Worker::persistent(None, channel, Client::new()).unwrap();
+        assert_eq!(restored.delayed(), 2);
+        assert!(restored.queue().is_empty());
+
+        std::thread::sleep(std::time::Duration::from_millis(600));
+        assert_eq!(restored.delayed(), 0);
+        assert_eq!(restored.queue().len(), 2);
+        assert_eq!(restored.backup().unwrap().load().unwrap().len(), 2);
+
+        restored.clock_in();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        assert!(restored.backup().unwrap().load().unwrap().is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_skips_undecodable() {
+        let channel = String::from("worker-undecodable");
+        let backup: Backup<Job> = Backup::new(Client::new(), &channel);
+        backup.clear().unwrap();
+
+        let raw = [
+            String::from("not json"),
+            String::from(r#"{"priority":"high"}"#),
+        ];
+        backup.push_raw(&raw).unwrap();
+        backup.push(&Job { priority: 2 }).unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        backup.delay(r#"{"rank":1}"#, later).unwrap();
+
+        let restored: Worker<Job> = Worker::persistent(None, channel, Client::new()).unwrap();
+        assert_eq!(restored.queue().len(), 1);
+        assert_eq!(restored.delayed(), 0);
+        assert!(restored.dead_letters().is_empty());
+
+        // Set aside in the dead-letter list, with why.
+        let mut client = Client::new();
+        let records: Vec<String> = client.connection.lrange(&backup.dead_key, 0, -1).unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(records
+            .iter()
+            .all(|record| record.contains("undecodable task")));
+        assert_eq!(backup.load().unwrap(), vec![Job { priority: 2 }]);
+
+        backup.clear().unwrap();
+    }
+}
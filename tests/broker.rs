@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    use serde::{Deserialize, Serialize};
+    use toretsu::broker::{Broker, FileBroker, MemoryBroker, RedisBroker};
+    use toretsu::client::Client;
+    use toretsu::retry::RetryPolicy;
+    use toretsu::task::{Failure, Task};
+    use toretsu::worker::Worker;
+
+    static PROCESSED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+    struct Job {
+        name: String,
+    }
+
+    impl Task for Job {
+        fn process(&mut self) {}
+
+        fn try_process(&mut self) -> Result<(), Failure> {
+            match self.name.as_str() {
+                "bad" => Err(Failure::from("bad job")),
+                _ => {
+                    PROCESSED.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }
+        }
+
+        fn retry_policy(&self) -> Option<RetryPolicy> {
+            Some(RetryPolicy::never())
+        }
+    }
+
+    fn temp_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("toretsu-{}", uuid::Uuid::new_v4()))
+    }
+
+    // What every backend promises, run against each one.
+    fn contract(broker: &dyn Broker) {
+        let timeout = Duration::from_millis(20);
+        assert_eq!(broker.consume("contract", timeout).unwrap(), None);
+
+        broker.publish("contract", b"first").unwrap();
+        broker.publish("contract", b"second").unwrap();
+        broker.publish("other", b"elsewhere").unwrap();
+
+        let first = broker.consume("contract", timeout).unwrap().unwrap();
+        assert_eq!(first.payload, b"first");
+        assert_eq!(first.channel, "contract");
+        assert_eq!(first.attempts, 1);
+
+        // Returned straight away, it's handed out again behind `second`.
+        broker.nack(&first, Duration::ZERO).unwrap();
+        let second = broker.consume("contract", timeout).unwrap().unwrap();
+        assert_eq!(second.payload, b"second");
+        let again = broker.consume("contract", timeout).unwrap().unwrap();
+        assert_eq!(again.payload, b"first");
+        assert_eq!(again.attempts, 2);
+
+        broker.ack(&second).unwrap();
+        broker.ack(&again).unwrap();
+        assert_eq!(broker.consume("contract", timeout).unwrap(), None);
+
+        let at = SystemTime::now() + Duration::from_millis(200);
+        broker.schedule("contract", b"later", at).unwrap();
+        assert_eq!(broker.consume("contract", timeout).unwrap(), None);
+        let later = broker
+            .consume("contract", Duration::from_secs(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(later.payload, b"later");
+        assert!(SystemTime::now() >= at);
+
+        broker.nack(&later, Duration::from_millis(100)).unwrap();
+        assert_eq!(broker.consume("contract", timeout).unwrap(), None);
+        let later = broker
+            .consume("contract", Duration::from_secs(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(later.attempts, 2);
+        broker.ack(&later).unwrap();
+
+        let elsewhere = broker.consume("other", timeout).unwrap().unwrap();
+        assert_eq!(elsewhere.payload, b"elsewhere");
+        broker.ack(&elsewhere).unwrap();
+    }
+
+    #[test]
+    fn memory_broker() {
+        let broker = MemoryBroker::new();
+        contract(&broker);
+        assert!(broker.is_empty("contract"));
+    }
+
+    #[test]
+    fn memory_broker_wakes_consumer() {
+        let broker = Arc::new(MemoryBroker::new());
+        let publisher = Arc::clone(&broker);
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            publisher.publish("wake", b"up").unwrap();
+        });
+
+        let message = broker.consume("wake", Duration::from_secs(5)).unwrap();
+        assert_eq!(message.unwrap().payload, b"up");
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn file_broker() {
+        let root = temp_dir();
+        let broker = FileBroker::open(&root).unwrap();
+        contract(&broker);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn file_broker_recovers_reserved() {
+        let root = temp_dir();
+        let broker = FileBroker::open(&root).unwrap();
+        broker.publish("crash", b"in flight").unwrap();
+        let taken = broker.consume("crash", Duration::ZERO).unwrap().unwrap();
+        assert_eq!(broker.consume("crash", Duration::ZERO).unwrap(), None);
+        drop(broker);
+
+        // A publisher opening the directory leaves reserved messages be.
+        let broker = FileBroker::open(&root).unwrap();
+        broker.publish("crash", b"new").unwrap();
+        let new = broker.consume("crash", Duration::ZERO).unwrap().unwrap();
+        assert_eq!(new.payload, b"new");
+        broker.ack(&new).unwrap();
+
+        assert_eq!(broker.recover().unwrap(), 1);
+        let again = broker.consume("crash", Duration::ZERO).unwrap().unwrap();
+        assert_eq!(again.id, taken.id);
+        assert_eq!(again.payload, b"in flight");
+        assert_eq!(again.attempts, 2);
+        broker.ack(&again).unwrap();
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn file_broker_encodes_channels() {
+        let root = temp_dir();
+        let broker = FileBroker::open(root.join("broker")).unwrap();
+        for channel in ["../escape", "orders/eu", "..", ""] {
+            broker.publish(channel, channel.as_bytes()).unwrap();
+        }
+
+        // Nothing was written outside the broker's root.
+        let outside: Vec<_> = std::fs::read_dir(&root).unwrap().collect();
+        assert_eq!(outside.len(), 1);
+        let mut dirs: Vec<String> = std::fs::read_dir(root.join("broker"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        dirs.sort_unstable();
+        assert_eq!(dirs, ["%", "%2E%2E", "%2E%2E%2Fescape", "orders%2Feu"]);
+
+        let taken = broker
+            .consume("orders/eu", Duration::ZERO)
+            .unwrap()
+            .unwrap();
+        assert_eq!(taken.payload, b"orders/eu");
+        drop(broker);
+
+        // Reserved messages in nested-looking channels are recovered too.
+        let broker = FileBroker::open(root.join("broker")).unwrap();
+        assert_eq!(broker.recover().unwrap(), 1);
+        for channel in ["../escape", "orders/eu", "..", ""] {
+            let message = broker.consume(channel, Duration::ZERO).unwrap().unwrap();
+            assert_eq!(message.payload, channel.as_bytes());
+            broker.ack(&message).unwrap();
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn worker_consume_from() {
+        let broker = Arc::new(MemoryBroker::new());
+        for name in ["one", "two", "bad", "three"] {
+            let raw = serde_json::to_vec(&Job {
+                name: name.to_string(),
+            })
+            .unwrap();
+            broker.publish("jobs", &raw).unwrap();
+        }
+        broker.publish("jobs", b"not a job").unwrap();
+
+        let mut worker: Worker<Job> = Worker::init(None, Some("jobs".to_string()), None);
+        worker.clock_in();
+        let listener = worker.consume_from(broker.clone(), 2);
+
+        std::thread::sleep(Duration::from_millis(500));
+        listener.shutdown().unwrap();
+
+        assert_eq!(PROCESSED.load(Ordering::SeqCst), 3);
+        assert_eq!(worker.dead_letters().len(), 1);
+        assert!(broker.is_empty("jobs"));
+    }
+
+    #[test]
+    #[ignore]
+    fn redis_broker() {
+        let broker = RedisBroker::new(Client::new()).unwrap();
+        let mut client = Client::new();
+        for channel in ["contract", "other"] {
+            let _: () = redis::cmd("DEL")
+                .arg(toretsu::streams::ConsumerGroup::generate_key(channel))
+                .arg(RedisBroker::generate_scheduled_key(channel))
+                .query(&mut client.connection)
+                .unwrap();
+        }
+
+        contract(&broker);
+    }
+}
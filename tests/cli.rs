@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use toretsu::cli::Command;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_worker() {
+        assert_eq!(parse(&["worker"]), Ok(Command::Worker(None)));
+        assert_eq!(
+            parse(&["worker", "emails"]),
+            Ok(Command::Worker(Some(String::from("emails"))))
+        );
+        assert!(parse(&["worker", "emails", "extra"]).is_err());
+    }
+
+    #[test]
+    fn parse_workers() {
+        assert_eq!(parse(&["workers", "3"]), Ok(Command::Workers(3)));
+        assert!(parse(&["workers"]).is_err());
+        assert!(parse(&["workers", "0"]).is_err());
+        assert!(parse(&["workers", "three"]).is_err());
+    }
+
+    #[test]
+    fn parse_help() {
+        assert_eq!(parse(&[]), Ok(Command::Help));
+        assert_eq!(parse(&["help"]), Ok(Command::Help));
+        assert_eq!(parse(&["--help"]), Ok(Command::Help));
+        assert!(parse(&["help", "worker"]).is_err());
+        assert!(parse(&["supervisor"]).is_err());
+    }
+
+    #[test]
+    fn command_channels() {
+        let channels = Command::Worker(Some(String::from("emails"))).channels();
+        assert_eq!(channels, [Some(String::from("emails"))]);
+
+        let channels = Command::Workers(3).channels();
+        assert_eq!(channels, [None, None, None]);
+
+        assert!(Command::Help.channels().is_empty());
+    }
+}
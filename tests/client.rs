@@ -67,9 +67,8 @@ mod tests {
                 assert!(m.is_ok());
                 let msg = m.unwrap();
                 let payload: redis::RedisResult<String> = msg.get_payload();
-                if msg.get_channel::<String>().is_ok() && payload.is_ok() {
+                if let (Ok(_), Ok(content)) = (msg.get_channel::<String>(), payload) {
                     let channel = msg.get_channel_name();
-                    let content = payload.unwrap();
                     assert_eq!(channel, "test");
                     assert_eq!(content, "Hello World!");
                     break;
@@ -77,4 +76,73 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    #[ignore]
+    fn client_listen() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let mut client = Client::new();
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&received);
+        let listener = client
+            .listen("listen-test", move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                redis::ControlFlow::<()>::Continue
+            })
+            .unwrap();
+
+        client.publish("listen-test", "one").unwrap();
+        client.publish("listen-test", "two").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+        assert!(!listener.is_finished());
+
+        listener.stop();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        assert!(listener.is_finished());
+        assert!(listener.join().is_ok());
+
+        let listener = client
+            .listen("listen-test", |_| redis::ControlFlow::Break(()))
+            .unwrap();
+        client.publish("listen-test", "three").unwrap();
+        assert!(listener.join().is_ok());
+    }
+
+    #[test]
+    #[ignore]
+    fn client_route() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use toretsu::router::Router;
+
+        let mut client = Client::new();
+        let orders = Arc::new(AtomicUsize::new(0));
+        let emails = Arc::new(AtomicUsize::new(0));
+
+        let mut router = Router::new();
+        let counter = Arc::clone(&orders);
+        router.route_pattern("route-orders.*", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            redis::ControlFlow::Continue
+        });
+        let counter = Arc::clone(&emails);
+        router.route("route-emails", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            redis::ControlFlow::Continue
+        });
+        let listener = client.route(router).unwrap();
+
+        client.publish("route-orders.eu", "1").unwrap();
+        client.publish("route-orders.us", "2").unwrap();
+        client.publish("route-emails", "3").unwrap();
+        client.publish("route-reports", "4").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        assert_eq!(orders.load(Ordering::SeqCst), 2);
+        assert_eq!(emails.load(Ordering::SeqCst), 1);
+        listener.shutdown().unwrap();
+    }
 }
@@ -2,6 +2,7 @@ mod tests {
     use confique::json5::{template as json5_template, FormatOptions as Json5FormatOptions};
     use confique::toml::{template as toml_template, FormatOptions as TomlFormatOptions};
     use confique::yaml::{template as yaml_template, FormatOptions as YamlFormatOptions};
+    use toretsu::broker::BrokerKind;
     use toretsu::config::Config;
 
     // #[test]
@@ -26,6 +27,15 @@ mod tests {
     fn new_conf() {
         let config = Config::new();
 
+        assert_eq!(config.redis_host, "localhost");
+        assert_eq!(config.redis_port, 6379);
+        assert_eq!(config.broker, BrokerKind::Redis);
+    }
+
+    #[test]
+    fn try_new_conf() {
+        let config = Config::try_new().unwrap();
+
         assert_eq!(config.redis_host, "localhost");
         assert_eq!(config.redis_port, 6379);
     }
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use toretsu::context::{CancellationToken, TaskContext};
+
+    #[test]
+    fn token_cancel() {
+        let token = CancellationToken::new();
+        let shared = token.clone();
+        assert!(!token.is_cancelled());
+        assert_eq!(token.deadline(), None);
+        assert_eq!(token.remaining(), None);
+
+        shared.cancel();
+        assert!(token.is_cancelled());
+        assert!(token.was_cancelled());
+        assert!(!token.is_expired());
+    }
+
+    #[test]
+    fn token_timeout() {
+        let token = CancellationToken::with_timeout(Duration::from_millis(50));
+        let ctx = TaskContext::new(token.clone());
+        assert!(!ctx.is_cancelled());
+        assert_eq!(token.timeout(), Some(Duration::from_millis(50)));
+        assert!(ctx.remaining().unwrap() <= Duration::from_millis(50));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(ctx.is_cancelled());
+        assert!(token.is_expired());
+        assert!(!token.was_cancelled());
+        assert_eq!(ctx.remaining(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn context_outside_worker() {
+        let ctx = TaskContext::new(CancellationToken::new());
+        assert_eq!(ctx.attempt(), 1);
+        assert_eq!(ctx.priority(), None);
+        assert!(ctx.worker_id().is_nil());
+        assert!(!ctx.task_id().is_nil());
+        assert_eq!(ctx.channel(), "");
+        assert_eq!(ctx.state::<String>(), None);
+
+        assert_eq!(ctx.progress(), 0.0);
+        ctx.set_progress(0.25);
+        assert_eq!(ctx.clone().progress(), 0.25);
+        ctx.set_progress(3.0);
+        assert_eq!(ctx.progress(), 1.0);
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use toretsu::client::Client;
+    use toretsu::control::Command;
+    use toretsu::error::Error;
+    use toretsu::task::Task;
+    use toretsu::worker::Worker;
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Noop;
+
+    impl Task for Noop {
+        fn process(&mut self) {}
+    }
+
+    #[test]
+    fn command_parse() {
+        assert_eq!(Command::generate_key("emails"), "toretsu:emails:control");
+        for command in [Command::Pause, Command::Resume] {
+            assert_eq!(command.to_string().parse::<Command>().unwrap(), command);
+        }
+        assert_eq!(" resume\n".parse::<Command>().unwrap(), Command::Resume);
+        assert!(matches!("stop".parse::<Command>(), Err(Error::Control(_))));
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_control() {
+        let worker: Worker<Noop> = Worker::init(None, Some(String::from("controlled")), None);
+        let listener = worker.control(&Client::new()).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+
+        let mut client = Client::new();
+        client.control("controlled", Command::Pause).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(worker.is_paused());
+
+        client
+            .publish(Command::generate_key("controlled"), "nonsense")
+            .unwrap();
+        client.control("controlled", Command::Resume).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!worker.is_paused());
+
+        listener.shutdown().unwrap();
+    }
+}
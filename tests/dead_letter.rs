@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use toretsu::dead_letter::DeadLetter;
+    use toretsu::entry::Entry;
+
+    #[test]
+    fn dead_letter_new() {
+        let mut entry = Entry::new(7);
+        entry.attempts = 3;
+        let id = entry.id;
+        let enqueued_at = entry.enqueued_at;
+
+        let letter = DeadLetter::new(entry, String::from("boom"));
+        assert_eq!(letter.id, id);
+        assert_eq!(letter.task, 7);
+        assert_eq!(letter.error, "boom");
+        assert_eq!(letter.attempts, 3);
+        assert_eq!(letter.enqueued_at, enqueued_at);
+        assert!(letter.failed_at >= enqueued_at);
+    }
+
+    #[test]
+    fn dead_letter_into_entry() {
+        let mut entry = Entry::new("replay me");
+        entry.attempts = 5;
+        let id = entry.id;
+
+        let entry = DeadLetter::new(entry, String::from("boom")).into_entry();
+        assert_eq!(entry.id, id);
+        assert_eq!(entry.task, "replay me");
+        assert_eq!(entry.attempts, 0);
+    }
+}
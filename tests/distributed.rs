@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+    use toretsu::client::Client;
+    use toretsu::distributed::{DistributedQueue, Priority};
+    use toretsu::envelope::Envelope;
+    use toretsu::registry::{Parcel, Registry};
+    use toretsu::task::Task;
+    use toretsu::worker::Worker;
+
+    static PROCESSED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+    struct Job {
+        priority: i64,
+        name: String,
+    }
+
+    impl Job {
+        fn new(priority: i64, name: &str) -> Self {
+            Self {
+                priority,
+                name: name.to_string(),
+            }
+        }
+    }
+
+    impl Priority for Job {
+        fn priority(&self) -> i64 {
+            self.priority
+        }
+    }
+
+    impl Task for Job {
+        fn process(&mut self) {
+            PROCESSED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn generate_key() {
+        assert_eq!(
+            DistributedQueue::<Job>::generate_key("jobs"),
+            "toretsu:jobs:zqueue"
+        );
+        assert_eq!(
+            DistributedQueue::<Job>::generate_rank_key("jobs"),
+            "toretsu:jobs:zqueue:rank"
+        );
+        assert_eq!(
+            DistributedQueue::<Job>::generate_dead_key("jobs"),
+            "toretsu:jobs:zqueue:dead"
+        );
+    }
+
+    #[test]
+    fn envelope_priority() {
+        let envelope = Envelope::new("job", -4, &Job::new(0, "a")).unwrap();
+        assert_eq!(envelope.priority(), -4);
+    }
+
+    #[test]
+    #[ignore]
+    fn distributed_queue_order() {
+        let mut queue: DistributedQueue<Job> =
+            DistributedQueue::init("zqueue-order", Client::new());
+        queue.clear().unwrap();
+        assert!(queue.is_empty().unwrap());
+
+        queue.push(&Job::new(1, "low")).unwrap();
+        queue
+            .extend(&[
+                Job::new(5, "first"),
+                Job::new(5, "second"),
+                Job::new(-2, "last"),
+            ])
+            .unwrap();
+        queue.push(&Job::new(5, "third")).unwrap();
+        assert_eq!(queue.len().unwrap(), 5);
+        assert_eq!(queue.peek().unwrap(), Some(Job::new(5, "first")));
+
+        // A second handle on the same queue sees the same order.
+        let mut other: DistributedQueue<Job> =
+            DistributedQueue::init("zqueue-order", Client::new());
+        assert_eq!(other.pop().unwrap(), Some(Job::new(5, "first")));
+
+        let names: Vec<String> = queue
+            .drain_sorted()
+            .unwrap()
+            .into_iter()
+            .map(|job| job.name)
+            .collect();
+        assert_eq!(names, vec!["second", "third", "low", "last"]);
+        assert_eq!(other.pop_timeout(Duration::from_millis(100)).unwrap(), None);
+
+        queue.clear().unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn distributed_queue_sets_aside_undecodable() {
+        let mut queue: DistributedQueue<Job> =
+            DistributedQueue::init("zqueue-undecodable", Client::new());
+        queue.clear().unwrap();
+        let mut client = Client::new();
+        let _: () = redis::cmd("ZADD")
+            .arg(&queue.key)
+            .arg(9)
+            .arg("0000000000000001:not a job")
+            .query(&mut client.connection)
+            .unwrap();
+        queue.push(&Job::new(1, "fine")).unwrap();
+
+        assert!(queue.pop().is_err());
+        assert_eq!(queue.pop().unwrap(), Some(Job::new(1, "fine")));
+        let dead: Vec<String> = redis::cmd("LRANGE")
+            .arg(&queue.dead_key)
+            .arg(0)
+            .arg(-1)
+            .query(&mut client.connection)
+            .unwrap();
+        assert_eq!(dead, ["0000000000000001:not a job"]);
+
+        queue.clear().unwrap();
+    }
+
+    #[test]
+    #[ignore]
+    fn workers_pull_from_distributed_queue() {
+        let mut queue: DistributedQueue<Job> =
+            DistributedQueue::init("zqueue-workers", Client::new());
+        queue.clear().unwrap();
+        let jobs: Vec<Job> = (0..20).map(|priority| Job::new(priority, "job")).collect();
+        queue.extend(&jobs).unwrap();
+
+        let mut first: Worker<Job> = Worker::new();
+        let mut second: Worker<Job> = Worker::new();
+        let pulling = [
+            first.pull(DistributedQueue::init("zqueue-workers", Client::new()), 4),
+            second.pull(DistributedQueue::init("zqueue-workers", Client::new()), 4),
+        ];
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(first.queue().len(), 4);
+        assert_eq!(second.queue().len(), 4);
+        assert_eq!(queue.len().unwrap(), 12);
+
+        first.clock_in();
+        second.clock_in();
+        std::thread::sleep(Duration::from_millis(1500));
+        assert_eq!(PROCESSED.load(Ordering::SeqCst), 20);
+        assert!(queue.is_empty().unwrap());
+
+        for puller in pulling {
+            puller.shutdown().unwrap();
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_pulls_envelopes() {
+        let mut queue: DistributedQueue<Envelope> =
+            DistributedQueue::init("zqueue-envelopes", Client::new());
+        queue.clear().unwrap();
+        queue
+            .push(&Envelope::new("job", 3, &Job::new(3, "enveloped")).unwrap())
+            .unwrap();
+
+        let mut registry = Registry::new();
+        registry.register::<Job>("job");
+        let mut worker: Worker<Parcel> = Worker::new();
+        let puller = worker.pull_with(queue, 8, move |envelope| registry.decode(envelope).ok());
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(worker.queue().len(), 1);
+
+        puller.shutdown().unwrap();
+    }
+}
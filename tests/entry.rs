@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use toretsu::entry::Entry;
+    use toretsu::queue::Queue;
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct Job {
+        priority: u32,
+        name: &'static str,
+    }
+
+    impl Ord for Job {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.priority.cmp(&other.priority)
+        }
+    }
+
+    impl PartialOrd for Job {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    #[test]
+    fn entry_new() {
+        let entry = Entry::new(Job {
+            priority: 1,
+            name: "a",
+        });
+        assert_eq!(entry.attempts, 0);
+        assert_eq!(entry.into_task().name, "a");
+    }
+
+    #[test]
+    fn entry_order() {
+        let jobs = [(1, "first"), (5, "second"), (1, "third"), (5, "fourth")]
+            .map(|(priority, name)| Entry::new(Job { priority, name }));
+
+        let mut queue = Queue::from(Vec::from(jobs));
+        let names: Vec<&str> = queue
+            .drain_sorted()
+            .into_iter()
+            .map(|entry| entry.task.name)
+            .collect();
+        assert_eq!(names, ["second", "fourth", "first", "third"]);
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+    use serde::{Deserialize, Serialize};
+    use toretsu::envelope::Envelope;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Email {
+        to: String,
+    }
+
+    #[test]
+    fn envelope_new() {
+        let email = Email {
+            to: String::from("ops@example.com"),
+        };
+        let envelope = Envelope::new("email", 5, &email).unwrap();
+        assert_eq!(envelope.kind, "email");
+        assert_eq!(envelope.priority, 5);
+        assert_eq!(envelope.payload["to"], "ops@example.com");
+    }
+
+    #[test]
+    fn envelope_json() {
+        let email = Email {
+            to: String::from("ops@example.com"),
+        };
+        let envelope = Envelope::new("email", 5, &email).unwrap();
+        let raw = envelope.to_json().unwrap();
+        let decoded = Envelope::from_json(&raw).unwrap();
+        assert_eq!(envelope, decoded);
+
+        let payload: Email = serde_json::from_value(decoded.payload).unwrap();
+        assert_eq!(payload, email);
+    }
+
+    #[test]
+    fn envelope_redis_value() {
+        let envelope = Envelope::new("email", -1, &Email { to: String::new() }).unwrap();
+        let args = envelope.to_redis_args();
+        assert_eq!(args.len(), 1);
+
+        let value = Value::Data(args[0].clone());
+        let decoded = Envelope::from_redis_value(&value).unwrap();
+        assert_eq!(envelope, decoded);
+
+        assert!(Envelope::from_redis_value(&Value::Nil).is_err());
+    }
+}
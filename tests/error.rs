@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use redis::{ErrorKind, RedisError};
+    use toretsu::error::Error;
+
+    #[test]
+    fn error_from_redis() {
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let err = Error::from(RedisError::from(refused));
+        assert!(matches!(err, Error::Connection(_)));
+        assert!(err.is_connection());
+
+        let err = Error::from(RedisError::from((ErrorKind::TypeError, "bad reply")));
+        assert!(matches!(err, Error::Protocol(_)));
+        assert!(!err.is_connection());
+        assert!(err.to_string().starts_with("redis error"));
+    }
+
+    #[test]
+    fn error_from_serde() {
+        let parsed = serde_json::from_str::<u32>("nope").unwrap_err();
+        let err = Error::from(parsed);
+        assert!(matches!(err, Error::Serialization(_)));
+        assert!(err.source().is_some());
+        assert!(err.to_string().starts_with("serialization failed"));
+    }
+}
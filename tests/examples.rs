@@ -3,6 +3,7 @@ mod tests {
     use rand::distributions::{Distribution, Uniform};
     use toretsu::task::Task;
     use toretsu::worker::Worker;
+    #[allow(unpredictable_function_pointer_comparisons)]
     #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
     struct Job<T> {
         value: T,
@@ -36,24 +37,24 @@ mod tests {
 
         let work = Vec::from(words);
         let mut worker = Worker::from(work);
-        assert_eq!(worker.queue.len(), 5);
+        assert_eq!(worker.queue().len(), 5);
 
         worker.clock_in();
-        assert!(worker.queue.is_empty());
+        assert!(worker.queue().is_empty());
 
         let more_work = ["else", "extern", "false", "fn", "for"]
             .map(|x| Job::new(x, callback))
             .to_vec();
 
-        worker.assign_many(more_work);
+        worker.assign_many(more_work).unwrap();
 
-        worker.assign_one(Job::new("if", callback));
-        worker.assign_one(Job::new("impl", callback));
+        worker.assign_one(Job::new("if", callback)).unwrap();
+        worker.assign_one(Job::new("impl", callback)).unwrap();
 
         worker.clock_in();
         worker.clock_out();
-        assert!(!worker.active);
-        assert!(worker.queue.is_empty());
+        assert!(!worker.is_active());
+        assert!(worker.queue().is_empty());
     }
 
     #[test]
@@ -73,13 +74,12 @@ mod tests {
         };
 
         let work = ints.into_iter().map(|x| Job::new(x, callback));
-        let more_work = work.clone();
         let mut worker = toretsu::worker::Worker::from(work.collect());
-        assert_eq!(worker.queue.len(), million);
+        assert_eq!(worker.queue().len(), million);
 
         worker.clock_in();
         worker.clock_out();
-        assert!(!worker.active);
-        assert!(worker.queue.is_empty());
+        assert!(!worker.is_active());
+        assert!(worker.queue().is_empty());
     }
 }
@@ -0,0 +1,45 @@
+#[cfg(all(test, feature = "exec"))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use toretsu::context::{CancellationToken, TaskContext};
+    use toretsu::envelope::Envelope;
+    use toretsu::exec::Exec;
+    use toretsu::registry::Registry;
+    use toretsu::task::Task;
+
+    #[test]
+    fn exec_builtin() {
+        let registry = Registry::builtin();
+        assert!(registry.contains(Exec::KIND));
+
+        // `args` can be left out.
+        let payload = serde_json::json!({ "program": "true" });
+        let envelope = Envelope::new(Exec::KIND, 1, &payload).unwrap();
+        let mut parcel = registry.decode(envelope).unwrap();
+        assert!(parcel.try_process().is_ok());
+    }
+
+    #[test]
+    fn exec_exit_status() {
+        assert!(Exec::new("sh", ["-c", "exit 0"]).try_process().is_ok());
+
+        let err = Exec::new("sh", ["-c", "exit 3"]).try_process().unwrap_err();
+        assert!(err.to_string().contains("exited"));
+
+        assert!(Exec::new("toretsu-missing-program", Vec::<String>::new())
+            .try_process()
+            .is_err());
+    }
+
+    #[test]
+    fn exec_cancelled() {
+        let ctx = TaskContext::new(CancellationToken::with_timeout(Duration::from_millis(100)));
+        let started = Instant::now();
+        let err = Exec::new("sleep", ["5"])
+            .try_process_with(&ctx)
+            .unwrap_err();
+        assert!(err.to_string().contains("stopped"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+    use toretsu::client::Client;
+    use toretsu::error::Error;
+    use toretsu::output::Produce;
+    use toretsu::results::ResultBackend;
+    use toretsu::task::{Failure, Task};
+    use toretsu::worker::Worker;
+    use uuid::Uuid;
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+    struct Square {
+        n: i64,
+        id: Option<Uuid>,
+        #[serde(skip)]
+        result: Option<i64>,
+    }
+
+    impl Square {
+        fn new(n: i64) -> Self {
+            Self {
+                n,
+                id: None,
+                result: None,
+            }
+        }
+    }
+
+    impl Task for Square {
+        fn process(&mut self) {}
+
+        fn try_process(&mut self) -> Result<(), Failure> {
+            match self.n {
+                n if n < 0 => Err(format!("can't square {n}").into()),
+                0 => Ok(()),
+                n => {
+                    self.result = Some(n * n);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl Produce for Square {
+        type Output = i64;
+
+        fn output(&mut self) -> Option<i64> {
+            self.result.take()
+        }
+
+        fn result_id(&self) -> Option<Uuid> {
+            self.id
+        }
+    }
+
+    #[test]
+    fn submit_and_wait() {
+        let mut worker = Worker::new();
+        let nine = worker.submit(Square::new(3)).unwrap();
+        let four = worker.submit(Square::new(2)).unwrap();
+
+        let nine = nine.try_wait().unwrap_err();
+        assert!(!nine.is_finished());
+
+        worker.clock_in();
+        assert_eq!(nine.wait().unwrap(), 9);
+        assert_eq!(
+            four.wait_timeout(Duration::from_secs(5)).unwrap().unwrap(),
+            4
+        );
+    }
+
+    #[test]
+    fn submit_failures() {
+        let mut worker = Worker::new();
+        worker.clock_in();
+
+        let failed = worker.submit(Square::new(-2)).unwrap();
+        let id = failed.id();
+        let err = failed.wait().unwrap_err();
+        assert!(matches!(&err, Error::Task(reason) if reason == "can't square -2"));
+        assert_eq!(worker.dead_letters()[0].id, id);
+
+        // Producing nothing is a failure like any other, dead letter and all.
+        let empty = worker.submit(Square::new(0)).unwrap();
+        let id = empty.id();
+        let err = empty.wait().unwrap_err();
+        assert_eq!(err.to_string(), "task failed: task produced no output");
+        let dead = worker.dead_letters();
+        assert_eq!(dead.len(), 2);
+        assert_eq!(dead[1].id, id);
+        assert_eq!(dead[1].error, "task produced no output");
+    }
+
+    #[test]
+    fn assigned_needs_no_output() {
+        let mut worker = Worker::new();
+        worker.clock_in();
+        worker.submit(Square::new(3)).unwrap().wait().unwrap();
+
+        // Nothing waits on an assigned task's output, so it can have none.
+        worker.assign_one(Square::new(0)).unwrap();
+        let until = std::time::Instant::now() + Duration::from_secs(5);
+        while worker.running() + worker.queue().len() > 0 && std::time::Instant::now() < until {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(worker.queue().is_empty());
+        assert!(worker.dead_letters().is_empty());
+    }
+
+    #[test]
+    fn submit_dropped() {
+        let mut worker = Worker::new();
+        let handle = worker.submit(Square::new(5)).unwrap();
+        drop(worker);
+
+        let err = handle.wait().unwrap_err();
+        assert!(matches!(err, Error::Task(reason) if reason.contains("dropped")));
+    }
+
+    #[test]
+    fn submit_result_id() {
+        let id = Uuid::new_v4();
+        let mut worker = Worker::new();
+        let handle = worker
+            .submit(Square {
+                id: Some(id),
+                ..Square::new(4)
+            })
+            .unwrap();
+        assert_eq!(handle.id(), id);
+
+        // Another task with the same result id is still a task of its own.
+        let again = worker
+            .submit(Square {
+                id: Some(id),
+                ..Square::new(5)
+            })
+            .unwrap();
+        assert_eq!(again.id(), id);
+
+        worker.clock_in();
+        assert_eq!(handle.wait().unwrap(), 16);
+        assert_eq!(again.wait().unwrap(), 25);
+
+        // Neither is left holding a slot, so shutting down doesn't wait.
+        let started = std::time::Instant::now();
+        assert!(worker.shutdown(Duration::from_secs(5)).is_clean());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn result_backend_key() {
+        assert_eq!(
+            ResultBackend::generate_key(&Uuid::nil()),
+            "toretsu:result:00000000-0000-0000-0000-000000000000"
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn result_backend() {
+        let backend = ResultBackend::new(Client::new()).with_ttl(Duration::from_secs(60));
+        assert_eq!(backend.ttl(), Duration::from_secs(60));
+
+        let mut worker = Worker::new().with_results(backend.clone());
+        worker.clock_in();
+
+        // Queued without a handle, as if it had come from another process.
+        let (ok, failed) = (Uuid::new_v4(), Uuid::new_v4());
+        for (id, n) in [(ok, 7), (failed, -1)] {
+            worker
+                .assign_one(Square {
+                    id: Some(id),
+                    ..Square::new(n)
+                })
+                .unwrap();
+        }
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(backend.wait::<i64>(ok, timeout).unwrap(), Some(49));
+        assert!(matches!(
+            backend.wait::<i64>(failed, timeout),
+            Err(Error::Task(_))
+        ));
+
+        assert!(backend.forget(ok).unwrap());
+        assert_eq!(backend.fetch::<i64>(ok).unwrap(), None);
+        backend.forget(failed).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+    use toretsu::context::{CancellationToken, TaskContext};
+    use toretsu::entry::Entry;
+    use toretsu::envelope::Envelope;
+    use toretsu::queue::Queue;
+    use toretsu::registry::Registry;
+    use toretsu::task::{Failure, Task};
+
+    static EMAILS: AtomicUsize = AtomicUsize::new(0);
+    static REPORTS: AtomicUsize = AtomicUsize::new(0);
+    static PROBES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Serialize, Deserialize)]
+    struct Email {
+        to: String,
+    }
+
+    impl Task for Email {
+        fn process(&mut self) {
+            EMAILS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Report {
+        rows: u32,
+    }
+
+    impl Task for Report {
+        fn process(&mut self) {
+            REPORTS.fetch_add(self.rows as usize, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Probe {
+        millis: u64,
+    }
+
+    impl Task for Probe {
+        fn process(&mut self) {}
+
+        fn try_process_with(&mut self, _ctx: &TaskContext) -> Result<(), Failure> {
+            PROBES.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_millis(self.millis))
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry
+            .register::<Email>("email")
+            .register::<Report>("report");
+
+        registry
+    }
+
+    #[test]
+    fn registry_register() {
+        let registry = registry();
+        assert!(registry.contains("email"));
+        assert!(registry.contains("report"));
+        assert!(!registry.contains("invoice"));
+        assert_eq!(registry.kinds().count(), 2);
+    }
+
+    #[test]
+    fn registry_decode() {
+        let registry = registry();
+        let email = Email {
+            to: String::from("ops@example.com"),
+        };
+        let raw = Envelope::new("email", 1, &email)
+            .unwrap()
+            .to_json()
+            .unwrap();
+
+        let mut parcel = registry.decode_json(&raw).unwrap();
+        assert_eq!(parcel.envelope.kind, "email");
+
+        let before = EMAILS.load(Ordering::SeqCst);
+        parcel.process();
+        assert_eq!(EMAILS.load(Ordering::SeqCst), before + 1);
+    }
+
+    #[test]
+    fn registry_decode_forwards() {
+        let mut registry = registry();
+        registry.register::<Probe>("probe");
+        let envelope = Envelope::new("probe", 1, &Probe { millis: 10 }).unwrap();
+
+        let mut parcel = registry.decode(envelope).unwrap();
+        assert_eq!(parcel.timeout(), Some(Duration::from_millis(10)));
+        assert_eq!(parcel.priority(), Some(1));
+
+        let ctx = TaskContext::new(CancellationToken::new());
+        parcel.try_process_with(&ctx).unwrap();
+        assert_eq!(PROBES.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn registry_decode_unknown() {
+        let registry = registry();
+        let envelope = Envelope::new("invoice", 1, &Report { rows: 1 }).unwrap();
+        let err = registry.decode(envelope).err().unwrap();
+        assert!(err.to_string().contains("invoice"));
+    }
+
+    #[test]
+    fn registry_decode_mismatch() {
+        let registry = registry();
+        let envelope = Envelope::new("report", 1, &Email { to: String::new() }).unwrap();
+        assert!(registry.decode(envelope).is_err());
+    }
+
+    #[test]
+    fn parcel_priority() {
+        let registry = registry();
+        let envelopes = [
+            Envelope::new("report", 2, &Report { rows: 10 }).unwrap(),
+            Envelope::new("email", 9, &Email { to: String::new() }).unwrap(),
+            Envelope::new("report", 5, &Report { rows: 20 }).unwrap(),
+        ];
+        let parcels = envelopes
+            .into_iter()
+            .map(|envelope| registry.decode(envelope).unwrap())
+            .collect();
+
+        let mut queue = Queue::from(parcels);
+        let priorities: Vec<i64> = queue
+            .drain_sorted()
+            .iter()
+            .map(|parcel| parcel.envelope.priority)
+            .collect();
+        assert_eq!(priorities, [9, 5, 2]);
+    }
+
+    #[test]
+    fn parcel_priority_ties_are_fifo() {
+        let registry = registry();
+        let entries = (0..8)
+            .map(|rows| Envelope::new("report", 1, &Report { rows }).unwrap())
+            .map(|envelope| Entry::new(registry.decode(envelope).unwrap()))
+            .collect();
+
+        let mut queue = Queue::from(entries);
+        let order: Vec<u32> = queue
+            .drain_sorted()
+            .into_iter()
+            .map(|entry| entry.task.envelope.payload["rows"].as_u64().unwrap() as u32)
+            .collect();
+        assert_eq!(order, (0..8).collect::<Vec<u32>>());
+    }
+}
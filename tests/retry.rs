@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use toretsu::retry::RetryPolicy;
+
+    #[test]
+    fn retry_never() {
+        let policy = RetryPolicy::never();
+        assert_eq!(policy.max_attempts, 1);
+        assert!(!policy.should_retry(1));
+        assert_eq!(RetryPolicy::default(), policy);
+    }
+
+    #[test]
+    fn retry_should_retry() {
+        let policy = RetryPolicy::new(3);
+        assert!(policy.should_retry(1));
+        assert!(policy.should_retry(2));
+        assert!(!policy.should_retry(3));
+
+        let policy = RetryPolicy::new(0);
+        assert_eq!(policy.max_attempts, 1);
+    }
+
+    #[test]
+    fn retry_backoff() {
+        let policy = RetryPolicy::new(10)
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(1))
+            .with_jitter(false);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(64), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retry_backoff_jitter() {
+        let policy = RetryPolicy::new(10)
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(10));
+
+        for attempts in 1..6 {
+            let ceiling = Duration::from_millis(100 << (attempts - 1));
+            let delay = policy.backoff(attempts);
+            assert!(delay >= ceiling / 2);
+            assert!(delay <= ceiling);
+        }
+    }
+}
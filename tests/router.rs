@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use redis::{ControlFlow, Msg, Value};
+    use toretsu::router::Router;
+    use toretsu::task::Task;
+    use toretsu::worker::Worker;
+
+    fn message(channel: &str, payload: &str) -> Msg {
+        let value = Value::Bulk(vec![
+            Value::Data(b"message".to_vec()),
+            Value::Data(channel.as_bytes().to_vec()),
+            Value::Data(payload.as_bytes().to_vec()),
+        ]);
+        Msg::from_value(&value).unwrap()
+    }
+
+    fn pmessage(pattern: &str, channel: &str, payload: &str) -> Msg {
+        let value = Value::Bulk(vec![
+            Value::Data(b"pmessage".to_vec()),
+            Value::Data(pattern.as_bytes().to_vec()),
+            Value::Data(channel.as_bytes().to_vec()),
+            Value::Data(payload.as_bytes().to_vec()),
+        ]);
+        Msg::from_value(&value).unwrap()
+    }
+
+    fn recorder(log: &Arc<Mutex<Vec<String>>>, name: &str) -> impl FnMut(&Msg) -> ControlFlow<()> {
+        let log = Arc::clone(log);
+        let name = name.to_string();
+        move |msg| {
+            let payload: String = msg.get_payload().unwrap();
+            log.lock().unwrap().push(format!("{name}:{payload}"));
+            ControlFlow::Continue
+        }
+    }
+
+    #[test]
+    fn router_dispatch() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut router = Router::new();
+        router
+            .route("orders", recorder(&log, "exact"))
+            .route_pattern("orders.*", recorder(&log, "orders"))
+            .route_pattern("emails.*", recorder(&log, "emails"));
+
+        let mut channels: Vec<&str> = router.channels().collect();
+        let mut patterns: Vec<&str> = router.patterns().collect();
+        channels.sort();
+        patterns.sort();
+        assert_eq!(channels, vec!["orders"]);
+        assert_eq!(patterns, vec!["emails.*", "orders.*"]);
+
+        let msgs = [
+            message("orders", "1"),
+            pmessage("orders.*", "orders.eu", "2"),
+            pmessage("emails.*", "emails.welcome", "3"),
+            message("reports", "4"),
+            pmessage("reports.*", "reports.daily", "5"),
+        ];
+        for msg in msgs.iter() {
+            assert!(matches!(router.dispatch(msg), ControlFlow::Continue));
+        }
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["exact:1", "orders:2", "emails:3"]
+        );
+    }
+
+    #[test]
+    fn router_drops_finished_routes() {
+        let mut router = Router::new();
+        router
+            .route("once", |_| ControlFlow::Break(()))
+            .route_pattern("twice.*", |msg| match msg.get_payload::<String>() {
+                Ok(payload) if payload == "last" => ControlFlow::Break(()),
+                _ => ControlFlow::Continue,
+            });
+
+        assert!(matches!(
+            router.dispatch(&message("once", "")),
+            ControlFlow::Continue
+        ));
+        assert_eq!(router.channels().count(), 0);
+        assert!(!router.is_empty());
+
+        assert!(matches!(
+            router.dispatch(&pmessage("twice.*", "twice.a", "first")),
+            ControlFlow::Continue
+        ));
+        assert!(matches!(
+            router.dispatch(&pmessage("twice.*", "twice.b", "last")),
+            ControlFlow::Break(())
+        ));
+        assert!(router.is_empty());
+    }
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    struct Order {
+        id: u32,
+    }
+
+    impl Task for Order {
+        fn process(&mut self) {}
+    }
+
+    #[test]
+    fn router_feeds_workers() {
+        let orders: Worker<Order> = Worker::new();
+        let mut router = Router::new();
+        router.route_pattern(
+            "orders.*",
+            orders.handler(|msg| msg.get_payload().ok().map(|id| Order { id })),
+        );
+
+        router.dispatch(&pmessage("orders.*", "orders.eu", "7"));
+        router.dispatch(&pmessage("orders.*", "orders.us", "not a number"));
+        router.dispatch(&pmessage("orders.*", "orders.us", "9"));
+        assert_eq!(orders.queue().len(), 2);
+        assert_eq!(orders.queue().peek().map(|entry| entry.task.id), Some(9));
+
+        drop(orders);
+        assert!(matches!(
+            router.dispatch(&pmessage("orders.*", "orders.eu", "1")),
+            ControlFlow::Break(())
+        ));
+    }
+}
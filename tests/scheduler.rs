@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use chrono::{TimeZone, Utc};
+    use toretsu::client::Client;
+    use toretsu::error::Error;
+    use toretsu::scheduler::{Cron, Schedule, Scheduler};
+    use toretsu::task::Task;
+    use toretsu::worker::Worker;
+
+    #[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+    struct Cleanup;
+
+    impl Task for Cleanup {
+        fn process(&mut self) {}
+    }
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> SystemTime {
+        let at = Utc.with_ymd_and_hms(year, month, day, hour, minute, 0);
+        SystemTime::from(at.unwrap())
+    }
+
+    #[test]
+    fn cron_parse() {
+        for valid in ["* * * * *", "*/15 9-17 * * 1-5", "0,30 2 1 1,7 7", "@daily"] {
+            assert!(valid.parse::<Cron>().is_ok(), "{valid}");
+        }
+
+        for invalid in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            let err = Cron::parse(invalid).unwrap_err();
+            assert!(matches!(err, Error::Schedule(_)), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn cron_next_after() {
+        // A Monday.
+        let start = at(2024, 1, 1, 0, 0) + Duration::from_secs(30);
+        let next = |expression: &str| Cron::parse(expression).unwrap().next_after(start);
+
+        assert_eq!(next("* * * * *"), Some(at(2024, 1, 1, 0, 1)));
+        assert_eq!(next("*/15 * * * *"), Some(at(2024, 1, 1, 0, 15)));
+        assert_eq!(next("0 2 * * *"), Some(at(2024, 1, 1, 2, 0)));
+        assert_eq!(next("30 9 * * 5"), Some(at(2024, 1, 5, 9, 30)));
+        assert_eq!(next("0 0 * * 7"), Some(at(2024, 1, 7, 0, 0)));
+        assert_eq!(next("0 0 29 2 *"), Some(at(2024, 2, 29, 0, 0)));
+        assert_eq!(next("0 0 13 * 5"), Some(at(2024, 1, 5, 0, 0)));
+        assert_eq!(next("@yearly"), Some(at(2025, 1, 1, 0, 0)));
+        assert_eq!(next("0 0 31 2 *"), None);
+    }
+
+    #[test]
+    fn interval_next_after() {
+        let schedule = Schedule::every(Duration::from_secs(60));
+        let start = UNIX_EPOCH + Duration::from_secs(90);
+        assert_eq!(
+            schedule.next_after(start),
+            Some(UNIX_EPOCH + Duration::from_secs(120))
+        );
+        assert_eq!(
+            schedule.next_after(UNIX_EPOCH + Duration::from_secs(120)),
+            Some(UNIX_EPOCH + Duration::from_secs(180))
+        );
+    }
+
+    #[test]
+    fn scheduler_run_pending() {
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&fired);
+        let worker: Worker<Cleanup> = Worker::new();
+
+        let mut scheduler = Scheduler::new();
+        scheduler
+            .add(
+                "count",
+                Schedule::every(Duration::from_millis(50)),
+                move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                },
+            )
+            .enqueue(
+                "cleanup",
+                Schedule::every(Duration::from_millis(50)),
+                &worker,
+                || Cleanup,
+            );
+        assert_eq!(scheduler.names().count(), 2);
+        assert_eq!(scheduler.last_run("count"), None);
+        assert_eq!(scheduler.run_pending().unwrap(), 0);
+
+        let next = scheduler.next_run("count").unwrap();
+        std::thread::sleep(next.duration_since(SystemTime::now()).unwrap_or_default());
+        assert_eq!(scheduler.run_pending().unwrap(), 2);
+        assert_eq!(scheduler.run_pending().unwrap(), 0);
+        assert_eq!(scheduler.last_run("count"), Some(next));
+        assert!(scheduler.next_run("count").unwrap() > next);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert_eq!(worker.queue().len(), 1);
+    }
+
+    #[test]
+    fn scheduler_start() {
+        let worker: Worker<Cleanup> = Worker::new();
+        let mut scheduler = Scheduler::new();
+        scheduler.enqueue(
+            "cleanup",
+            Schedule::every(Duration::from_millis(100)),
+            &worker,
+            || Cleanup,
+        );
+
+        let listener = scheduler.start();
+        std::thread::sleep(Duration::from_millis(450));
+        listener.shutdown().unwrap();
+
+        let queued = worker.queue().len();
+        assert!((3..=5).contains(&queued), "{queued}");
+    }
+
+    #[test]
+    #[ignore]
+    fn scheduler_fires_once_across_instances() {
+        let mut client = Client::new();
+        let _: () = redis::cmd("HDEL")
+            .arg(Scheduler::generate_key())
+            .arg("shared")
+            .query(&mut client.connection)
+            .unwrap();
+
+        let fired = Arc::new(AtomicUsize::new(0));
+        let mut schedulers: Vec<Scheduler> = (0..3)
+            .map(|_| {
+                let counter = Arc::clone(&fired);
+                let mut scheduler = Scheduler::new().with_client(Client::new());
+                scheduler.add(
+                    "shared",
+                    Schedule::every(Duration::from_millis(200)),
+                    move || {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    },
+                );
+                scheduler
+            })
+            .collect();
+
+        std::thread::sleep(Duration::from_millis(250));
+        for scheduler in schedulers.iter_mut() {
+            scheduler.run_pending().unwrap();
+        }
+
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert!(Scheduler::last_runs(&mut client)
+            .unwrap()
+            .contains_key("shared"));
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use redis::streams::{StreamReadOptions, StreamReadReply};
+    use redis::Commands;
+    use serde::{Deserialize, Serialize};
+    use toretsu::client::Client;
+    use toretsu::streams::ConsumerGroup;
+    use toretsu::task::Task;
+    use toretsu::worker::Worker;
+
+    #[test]
+    fn consumer_group_new() {
+        let group = ConsumerGroup::new("billing");
+        assert_eq!(group.name, "billing");
+        assert_eq!(group.batch, 16);
+        assert_eq!(group.min_idle, Duration::from_secs(60));
+
+        let group = group
+            .with_batch(0)
+            .with_min_idle(Duration::from_millis(500));
+        assert_eq!(group.batch, 1);
+        assert_eq!(group.min_idle, Duration::from_millis(500));
+        assert_eq!(
+            ConsumerGroup::init("billing", 1, Duration::from_millis(500)),
+            group
+        );
+    }
+
+    #[test]
+    fn generate_key() {
+        let key = ConsumerGroup::generate_key("invoices");
+        assert_eq!(key, "toretsu:invoices:stream");
+    }
+
+    static BILLED: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+    struct Invoice {
+        cents: u32,
+    }
+
+    impl Task for Invoice {
+        fn process(&mut self) {
+            BILLED.fetch_add(self.cents, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn worker_consumes_and_reclaims() {
+        let channel = "streams-billing";
+        let mut client = Client::new();
+        let key = ConsumerGroup::generate_key(channel);
+        let _: () = client.connection.del(&key).unwrap();
+
+        // A consumer that reads an entry and dies before acknowledging it.
+        let _: () = client
+            .connection
+            .xgroup_create_mkstream(&key, "billing", "0")
+            .unwrap();
+        client.send(channel, &Invoice { cents: 100 }).unwrap();
+        let options = StreamReadOptions::default().group("billing", "dead-worker");
+        let _: StreamReadReply = client
+            .connection
+            .xread_options(&[&key], &[">"], &options)
+            .unwrap();
+        assert_eq!(client.pending(channel, "billing").unwrap(), 1);
+
+        let group = ConsumerGroup::new("billing").with_min_idle(Duration::from_millis(200));
+        let mut worker: Worker<Invoice> = Worker::init(None, Some(channel.to_string()), None);
+        let consumer = worker.consume(&Client::new(), group).unwrap();
+        client.send(channel, &Invoice { cents: 20 }).unwrap();
+        client.send(channel, &Invoice { cents: 3 }).unwrap();
+
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(BILLED.load(Ordering::SeqCst), 0);
+        assert_eq!(client.pending(channel, "billing").unwrap(), 3);
+
+        worker.clock_in();
+        std::thread::sleep(Duration::from_millis(1000));
+        assert_eq!(BILLED.load(Ordering::SeqCst), 123);
+        assert_eq!(client.pending(channel, "billing").unwrap(), 0);
+        let len: usize = client.connection.xlen(&key).unwrap();
+        assert_eq!(len, 0);
+
+        consumer.shutdown().unwrap();
+        let _: () = client.connection.del(&key).unwrap();
+    }
+}
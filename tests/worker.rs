@@ -1,10 +1,20 @@
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+
+    use serde::{Deserialize, Serialize};
+    use toretsu::client::Client;
+    use toretsu::context::TaskContext;
+    use toretsu::dead_letter::DeadLetter;
     use toretsu::queue::Queue;
-    use toretsu::task::Task;
+    use toretsu::retry::RetryPolicy;
+    use toretsu::task::{Failure, Task};
     use toretsu::worker::Worker;
     use uuid::Uuid;
 
+    #[allow(unpredictable_function_pointer_comparisons)]
     #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
     pub struct Job<T, U, RV> {
         kwargs: T,
@@ -25,15 +35,8 @@ mod tests {
             args: Option<U>,
             result: Option<RV>,
         ) -> Self {
-            let args = match args {
-                Some(args) => args,
-                None => U::default(),
-            };
-
-            let result = match result {
-                Some(result) => result,
-                None => RV::default(),
-            };
+            let args = args.unwrap_or_default();
+            let result = result.unwrap_or_default();
 
             Self {
                 kwargs,
@@ -93,7 +96,7 @@ mod tests {
 
         let vector = Vec::from(vec);
         let worker = Worker::from(vector);
-        assert_eq!(worker.queue.len(), 10);
+        assert_eq!(worker.queue().len(), 10);
     }
 
     #[test]
@@ -103,13 +106,13 @@ mod tests {
 
         let vector = Vec::from(vec);
         let mut worker = Worker::from(vector);
-        assert_eq!(worker.queue.len(), 10);
+        assert_eq!(worker.queue().len(), 10);
 
         worker.clock_in();
-        assert!(worker.queue.is_empty());
+        assert!(worker.queue().is_empty());
 
         worker.clock_out();
-        assert!(!worker.active);
+        assert!(!worker.is_active());
     }
 
     #[test]
@@ -119,21 +122,721 @@ mod tests {
 
         let vector = Vec::from(vec);
         let mut worker = Worker::from(vector);
-        assert_eq!(worker.queue.len(), 10);
+        assert_eq!(worker.queue().len(), 10);
 
         worker.clock_in();
-        assert!(worker.queue.is_empty());
+        assert!(worker.queue().is_empty());
 
         let w = [3, 5, 14, 2, 12, 18, 17, 11, 16, 6].map(|x| Job::new(x, callback));
-        worker.assign_one(Job::new(21, callback));
+        worker.assign_one(Job::new(21, callback)).unwrap();
         let work = Vec::from(w);
-        worker.assign_many(work);
+        worker.assign_many(work).unwrap();
 
         worker.clock_in();
         worker.clock_out();
-        assert!(!worker.active);
-        assert!(worker.queue.is_empty());
+        assert!(!worker.is_active());
+        assert!(worker.queue().is_empty());
 
         std::thread::sleep(std::time::Duration::from_millis(1000));
     }
+
+    static RECEIVED: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+    struct Ping {
+        value: u32,
+    }
+
+    impl Task for Ping {
+        fn process(&mut self) {
+            RECEIVED.fetch_add(self.value, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn test_worker_listen() {
+        let mut worker: Worker<Ping> = Worker::new();
+        let listener = worker.listen(&Client::new()).unwrap();
+        worker.clock_in();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let mut client = Client::new();
+        for value in [1, 2, 3] {
+            let raw = serde_json::to_string(&Ping { value }).unwrap();
+            client.publish(&worker.channel, raw).unwrap();
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        assert_eq!(RECEIVED.load(Ordering::SeqCst), 6);
+
+        listener.shutdown().unwrap();
+        assert!(worker.queue().is_empty());
+
+        worker.clock_out();
+        let raw = serde_json::to_string(&Ping { value: 4 }).unwrap();
+        client.publish(&worker.channel, raw).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        assert_eq!(RECEIVED.load(Ordering::SeqCst), 6);
+        assert_eq!(worker.queue().len(), 1);
+    }
+
+    static FINISHED: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    struct Tick {
+        value: u32,
+    }
+
+    impl Task for Tick {
+        fn process(&mut self) {
+            FINISHED.fetch_add(self.value, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_worker_continuous() {
+        let mut worker = Worker::new();
+        assert!(!worker.is_active());
+
+        worker.assign_one(Tick { value: 1 }).unwrap();
+        assert_eq!(worker.queue().len(), 1);
+
+        worker.clock_in();
+        worker.assign_one(Tick { value: 2 }).unwrap();
+        worker
+            .assign_many([3, 4].map(|value| Tick { value }))
+            .unwrap();
+        assert!(worker.queue().is_empty());
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(FINISHED.load(Ordering::SeqCst), 10);
+        assert_eq!(worker.running(), 0);
+
+        worker.clock_out();
+        worker.assign_one(Tick { value: 5 }).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(FINISHED.load(Ordering::SeqCst), 10);
+        assert_eq!(worker.queue().len(), 1);
+
+        worker.clock_in();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(FINISHED.load(Ordering::SeqCst), 15);
+        assert!(worker.queue().is_empty());
+    }
+
+    static STARTED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Slow {
+        priority: u32,
+    }
+
+    impl Task for Slow {
+        fn process(&mut self) {
+            STARTED.lock().unwrap().push(self.priority);
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn test_worker_concurrency() {
+        let mut worker = Worker::new().with_concurrency(1);
+        assert_eq!(worker.concurrency(), Some(1));
+
+        worker.assign_one(Slow { priority: 50 }).unwrap();
+        worker.clock_in();
+        worker
+            .assign_many([1, 4, 2, 3].map(|priority| Slow { priority }))
+            .unwrap();
+        assert_eq!(worker.running(), 1);
+        assert_eq!(worker.queue().len(), 4);
+
+        worker.assign_one(Slow { priority: 100 }).unwrap();
+        assert_eq!(
+            worker.queue().peek().map(|entry| &entry.task),
+            Some(&Slow { priority: 100 })
+        );
+
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(worker.running(), 0);
+        assert!(worker.queue().is_empty());
+
+        let started = STARTED.lock().unwrap().clone();
+        assert_eq!(started, [50, 100, 4, 3, 2, 1]);
+
+        worker.set_concurrency(Some(0));
+        assert_eq!(worker.concurrency(), Some(1));
+
+        worker.set_concurrency(None);
+        assert_eq!(worker.concurrency(), None);
+    }
+
+    static THREADS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    enum Chore {
+        Block(u64),
+        Record,
+    }
+
+    impl Task for Chore {
+        fn process(&mut self) {
+            match self {
+                Chore::Block(millis) => std::thread::sleep(Duration::from_millis(*millis)),
+                Chore::Record => {
+                    let name = std::thread::current()
+                        .name()
+                        .unwrap_or_default()
+                        .to_string();
+                    THREADS.lock().unwrap().push(name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_worker_pool() {
+        let mut blocked = Worker::init(None, Some(String::from("blocked")), None)
+            .with_threads(1)
+            .unwrap();
+        assert_eq!(blocked.threads(), 1);
+
+        let mut free = Worker::init(None, Some(String::from("free")), None)
+            .with_threads(2)
+            .unwrap();
+        assert_eq!(free.threads(), 2);
+
+        blocked.clock_in();
+        blocked.assign_one(Chore::Block(1000)).unwrap();
+
+        free.clock_in();
+        free.assign_many([Chore::Record, Chore::Record]).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let names = THREADS.lock().unwrap().clone();
+        assert_eq!(names.len(), 2);
+        assert!(names.iter().all(|name| name.starts_with("free-")));
+        assert_eq!(blocked.running(), 1);
+
+        let pool = Arc::new(Worker::<Chore>::build_pool("shared", 3).unwrap());
+        let one: Worker<Chore> = Worker::new().with_pool(Arc::clone(&pool));
+        let two: Worker<Chore> = Worker::new().with_pool(pool);
+        assert_eq!(one.threads(), 3);
+        assert_eq!(two.threads(), 3);
+
+        let shared: Worker<Chore> = Worker::new();
+        assert_eq!(shared.threads(), rayon::current_num_threads());
+        let own: Worker<Chore> = Worker::try_init(None, None, None).unwrap();
+        assert_eq!(own.threads(), shared.threads());
+    }
+
+    static FLAKY: AtomicU32 = AtomicU32::new(0);
+    static PANICKY: AtomicU32 = AtomicU32::new(0);
+    static STUBBORN: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Eq, PartialEq, Ord, PartialOrd)]
+    enum Unreliable {
+        // Fails until it has been tried this many times.
+        Flaky(u32),
+        Panicky,
+        // Always fails, but only allows itself two attempts.
+        Stubborn,
+    }
+
+    impl Task for Unreliable {
+        fn process(&mut self) {
+            let _ = self.try_process();
+        }
+
+        fn try_process(&mut self) -> Result<(), Failure> {
+            match self {
+                Unreliable::Flaky(succeeds_on) => {
+                    let attempt = FLAKY.fetch_add(1, Ordering::SeqCst) + 1;
+                    match attempt >= *succeeds_on {
+                        true => Ok(()),
+                        false => Err(format!("attempt {attempt} failed").into()),
+                    }
+                }
+                Unreliable::Panicky => {
+                    PANICKY.fetch_add(1, Ordering::SeqCst);
+                    panic!("downstream exploded");
+                }
+                Unreliable::Stubborn => {
+                    STUBBORN.fetch_add(1, Ordering::SeqCst);
+                    Err("still down".into())
+                }
+            }
+        }
+
+        fn retry_policy(&self) -> Option<RetryPolicy> {
+            match self {
+                Unreliable::Stubborn => Some(RetryPolicy::new(2).with_jitter(false)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_worker_retry() {
+        let policy = RetryPolicy::new(4)
+            .with_base_delay(Duration::from_millis(20))
+            .with_jitter(false);
+        let mut worker = Worker::new().with_retry_policy(policy);
+        assert_eq!(worker.retry_policy(), policy);
+
+        worker.clock_in();
+        worker
+            .assign_many([
+                Unreliable::Flaky(3),
+                Unreliable::Panicky,
+                Unreliable::Stubborn,
+            ])
+            .unwrap();
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(worker.delayed() > 0);
+
+        std::thread::sleep(Duration::from_millis(1000));
+        assert_eq!(FLAKY.load(Ordering::SeqCst), 3);
+        assert_eq!(PANICKY.load(Ordering::SeqCst), 4);
+        assert_eq!(STUBBORN.load(Ordering::SeqCst), 2);
+        assert_eq!(worker.delayed(), 0);
+        assert_eq!(worker.running(), 0);
+        assert!(worker.queue().is_empty());
+    }
+
+    static FIXED: AtomicU32 = AtomicU32::new(0);
+    static REPLAYED: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    enum Broken {
+        Error(u32),
+        Panic,
+    }
+
+    impl Task for Broken {
+        fn process(&mut self) {
+            let _ = self.try_process();
+        }
+
+        fn try_process(&mut self) -> Result<(), Failure> {
+            if FIXED.load(Ordering::SeqCst) > 0 {
+                REPLAYED.fetch_add(1, Ordering::SeqCst);
+                return Ok(());
+            }
+
+            match self {
+                Broken::Error(code) => Err(format!("error {code}").into()),
+                Broken::Panic => panic!("bug in task"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_worker_dead_letters() {
+        let mut worker = Worker::new();
+        worker.clock_in();
+        worker
+            .assign_many([Broken::Error(1), Broken::Error(2), Broken::Panic])
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let (first, second) = {
+            let dead = worker.dead_letters();
+            assert_eq!(dead.len(), 3);
+            assert!(dead.iter().all(|letter| letter.attempts == 1));
+            assert!(dead
+                .iter()
+                .all(|letter| letter.failed_at >= letter.enqueued_at));
+
+            let panicked = dead.iter().find(|letter| letter.task == Broken::Panic);
+            assert!(panicked.unwrap().error.contains("bug in task"));
+
+            let errors: Vec<&DeadLetter<Broken>> = dead
+                .iter()
+                .filter(|letter| letter.task != Broken::Panic)
+                .collect();
+            assert!(errors.iter().any(|letter| letter.error == "error 1"));
+            (errors[0].id, errors[1].id)
+        };
+
+        assert!(worker.purge_dead_letter(first).unwrap());
+        assert!(!worker.purge_dead_letter(first).unwrap());
+        assert_eq!(worker.dead_letters().len(), 2);
+
+        FIXED.store(1, Ordering::SeqCst);
+        assert!(worker.requeue_dead_letter(second).unwrap());
+        assert!(!worker.requeue_dead_letter(second).unwrap());
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(REPLAYED.load(Ordering::SeqCst), 1);
+        assert_eq!(worker.dead_letters().len(), 1);
+
+        worker.clock_out();
+        assert_eq!(worker.requeue_dead_letters().unwrap(), 1);
+        assert!(worker.dead_letters().is_empty());
+        assert_eq!(worker.queue().len(), 1);
+        assert_eq!(worker.queue().peek().unwrap().attempts, 0);
+
+        worker.queue().clear();
+        assert_eq!(worker.purge_dead_letters().unwrap(), 0);
+    }
+
+    static SENT: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Reminder(u32);
+
+    impl Task for Reminder {
+        fn process(&mut self) {
+            SENT.lock().unwrap().push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_worker_assign_later() {
+        let mut worker = Worker::new();
+        worker
+            .assign_after(Reminder(1), Duration::from_millis(100))
+            .unwrap();
+        worker
+            .assign_after(Reminder(2), Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(worker.delayed(), 2);
+
+        // Due tasks wait in the queue while the worker is clocked out, in
+        // priority order.
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(worker.delayed(), 0);
+        assert_eq!(worker.queue().len(), 2);
+        assert_eq!(worker.queue().peek().unwrap().task, Reminder(2));
+        worker.queue().clear();
+
+        worker.clock_in();
+        let past = SystemTime::now() - Duration::from_secs(1);
+        worker.assign_at(Reminder(3), past).unwrap();
+        worker
+            .assign_at(Reminder(4), SystemTime::now() + Duration::from_millis(300))
+            .unwrap();
+        worker.assign_one(Reminder(5)).unwrap();
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(!SENT.lock().unwrap().contains(&4));
+        assert_eq!(worker.delayed(), 1);
+
+        std::thread::sleep(Duration::from_millis(450));
+        let mut sent = SENT.lock().unwrap().clone();
+        sent.sort();
+        assert_eq!(sent, vec![3, 4, 5]);
+        assert_eq!(worker.delayed(), 0);
+    }
+
+    static SYNCED: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    enum Transfer {
+        // Polls its context until it's told to stop.
+        Patient(u64),
+        // Ignores its context altogether.
+        Stuck(u64),
+        Quick,
+    }
+
+    impl Task for Transfer {
+        fn process(&mut self) {}
+
+        fn try_process_with(&mut self, ctx: &TaskContext) -> Result<(), Failure> {
+            match self {
+                Transfer::Patient(_) => {
+                    while !ctx.is_cancelled() {
+                        std::thread::sleep(Duration::from_millis(5));
+                    }
+                    Ok(())
+                }
+                Transfer::Stuck(millis) => {
+                    std::thread::sleep(Duration::from_millis(*millis));
+                    Ok(())
+                }
+                Transfer::Quick => {
+                    SYNCED.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            match self {
+                Transfer::Patient(millis) => Some(Duration::from_millis(*millis)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_worker_timeout() {
+        let policy = RetryPolicy::new(2).with_base_delay(Duration::ZERO);
+        let mut worker = Worker::new()
+            .with_timeout(Duration::from_millis(100))
+            .with_retry_policy(policy);
+        assert_eq!(worker.timeout(), Some(Duration::from_millis(100)));
+
+        worker.clock_in();
+        worker
+            .assign_many([Transfer::Patient(50), Transfer::Stuck(300), Transfer::Quick])
+            .unwrap();
+
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(worker.overdue(), 1);
+        assert_eq!(SYNCED.load(Ordering::SeqCst), 1);
+
+        std::thread::sleep(Duration::from_millis(700));
+        assert_eq!(worker.overdue(), 0);
+        assert_eq!(worker.running(), 0);
+
+        let dead = worker.dead_letters();
+        assert_eq!(dead.len(), 2);
+        assert!(dead.iter().all(|letter| letter.attempts == 2));
+        let patient = dead
+            .iter()
+            .find(|letter| letter.task == Transfer::Patient(50));
+        assert_eq!(patient.unwrap().error, "task timed out after 50ms");
+        let stuck = dead
+            .iter()
+            .find(|letter| letter.task == Transfer::Stuck(300));
+        assert_eq!(stuck.unwrap().error, "task timed out after 100ms");
+    }
+
+    #[test]
+    fn test_worker_cancel() {
+        let mut worker = Worker::new().with_retry_policy(RetryPolicy::new(3));
+        worker.assign_one(Transfer::Patient(60_000)).unwrap();
+        let id = worker.queue().peek().unwrap().id;
+        assert!(!worker.cancel(id));
+
+        worker.clock_in();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(worker.running(), 1);
+        assert!(worker.cancel(id));
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(worker.running(), 0);
+        let dead = worker.dead_letters();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 1);
+        assert_eq!(dead[0].error, "task was cancelled");
+    }
+
+    static SEEN: Mutex<Vec<(Uuid, u32, Uuid, String)>> = Mutex::new(Vec::new());
+
+    struct Settings {
+        endpoint: &'static str,
+    }
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Upload(u32);
+
+    impl Task for Upload {
+        fn process(&mut self) {}
+
+        fn try_process_with(&mut self, ctx: &TaskContext) -> Result<(), Failure> {
+            let endpoint = ctx.state::<Settings>().map(|settings| settings.endpoint);
+            ctx.log(&format!("uploading to {}", endpoint.unwrap_or("nowhere")));
+            SEEN.lock().unwrap().push((
+                ctx.task_id(),
+                ctx.attempt(),
+                ctx.worker_id(),
+                ctx.channel().to_string(),
+            ));
+            assert!(ctx.enqueued_at() <= SystemTime::now());
+            assert_eq!(ctx.priority(), Some(i64::from(self.0)));
+
+            ctx.set_progress(0.5);
+            std::thread::sleep(Duration::from_millis(100));
+            match ctx.attempt() {
+                1 => Err("try again".into()),
+                _ => Ok(()),
+            }
+        }
+
+        fn priority(&self) -> Option<i64> {
+            Some(i64::from(self.0))
+        }
+    }
+
+    #[test]
+    fn test_worker_context() {
+        let logged: Arc<Mutex<Vec<String>>> = Arc::default();
+        let log = Arc::clone(&logged);
+        let policy = RetryPolicy::new(2).with_base_delay(Duration::ZERO);
+        let mut worker = Worker::init(None, Some(String::from("uploads")), None)
+            .with_retry_policy(policy)
+            .with_state(Settings {
+                endpoint: "https://example.com",
+            })
+            .with_logger(move |ctx, message| {
+                log.lock()
+                    .unwrap()
+                    .push(format!("{}: {message}", ctx.task_id()));
+            });
+
+        worker.assign_one(Upload(1)).unwrap();
+        let id = worker.queue().peek().unwrap().id;
+        assert_eq!(worker.progress(id), None);
+
+        worker.clock_in();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(worker.progress(id), Some(0.5));
+
+        std::thread::sleep(Duration::from_millis(250));
+        assert_eq!(worker.progress(id), None);
+        assert!(worker.dead_letters().is_empty());
+
+        let seen = SEEN.lock().unwrap();
+        let attempts: Vec<u32> = seen.iter().map(|(_, attempt, _, _)| *attempt).collect();
+        assert_eq!(attempts, vec![1, 2]);
+        assert!(seen.iter().all(|(task, _, worker_id, channel)| {
+            *task == id && *worker_id == worker.id && channel == "uploads"
+        }));
+        assert_eq!(
+            *logged.lock().unwrap(),
+            vec![format!("{id}: uploading to https://example.com"); 2]
+        );
+    }
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    enum Deploy {
+        Waiting(u8),
+        Short,
+        Long,
+    }
+
+    impl Task for Deploy {
+        fn process(&mut self) {}
+
+        fn try_process_with(&mut self, ctx: &TaskContext) -> Result<(), Failure> {
+            match self {
+                Self::Long => {
+                    while !ctx.is_cancelled() {
+                        std::thread::sleep(Duration::from_millis(5));
+                    }
+                    Err("interrupted".into())
+                }
+                Self::Short => {
+                    std::thread::sleep(Duration::from_millis(50));
+                    Ok(())
+                }
+                Self::Waiting(_) => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_worker_shutdown() {
+        let mut worker = Worker::new().with_threads(2).unwrap().with_concurrency(2);
+        worker
+            .assign_many([
+                Deploy::Waiting(1),
+                Deploy::Long,
+                Deploy::Short,
+                Deploy::Waiting(2),
+            ])
+            .unwrap();
+        worker
+            .assign_after(Deploy::Waiting(3), Duration::from_secs(60))
+            .unwrap();
+        let long = worker.queue().peek().unwrap().id;
+
+        worker.clock_in();
+        std::thread::sleep(Duration::from_millis(20));
+        let report = worker.shutdown(Duration::from_millis(300));
+
+        assert!(worker.is_closed());
+        assert!(!report.is_clean());
+        assert_eq!(report.finished, 1);
+        assert_eq!(report.interrupted, vec![long]);
+        let pending: Vec<Deploy> = report.pending.into_iter().map(|entry| entry.task).collect();
+        assert_eq!(
+            pending,
+            vec![Deploy::Waiting(2), Deploy::Waiting(1), Deploy::Waiting(3)]
+        );
+        assert!(matches!(
+            worker.assign_one(Deploy::Short),
+            Err(toretsu::error::Error::Closed)
+        ));
+
+        // Cancelled by the shutdown, so it's let go rather than dead-lettered.
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(worker.running(), 0);
+        assert!(worker.dead_letters().is_empty());
+        assert_eq!(worker.delayed(), 0);
+
+        let mut worker = Worker::from(vec![Deploy::Short]);
+        worker.clock_in();
+        let report = worker.shutdown(Duration::from_secs(5));
+        assert!(report.is_clean());
+        assert_eq!(report.finished, 1);
+        assert!(report.pending.is_empty());
+
+        // With one thread, the tasks behind the first are handed to the pool
+        // but never get to start, so they come back rather than being lost.
+        let mut worker = Worker::from(vec![Deploy::Long, Deploy::Short, Deploy::Waiting(1)])
+            .with_threads(1)
+            .unwrap();
+        worker.clock_in();
+        std::thread::sleep(Duration::from_millis(20));
+        let report = worker.shutdown(Duration::from_millis(50));
+        assert_eq!(report.finished, 0);
+        assert_eq!(report.interrupted.len(), 1);
+        let pending: Vec<Deploy> = report.pending.into_iter().map(|entry| entry.task).collect();
+        assert_eq!(pending, vec![Deploy::Short, Deploy::Waiting(1)]);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(worker.running(), 0);
+        assert!(worker.dead_letters().is_empty());
+    }
+
+    static EXPORTED: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Export(u8);
+
+    impl Task for Export {
+        fn process(&mut self) {
+            if self.0 == 9 {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            EXPORTED.lock().unwrap().push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_worker_pause() {
+        // One thread and no concurrency limit, so clocking in hands every
+        // task to the pool at once.
+        let mut worker = Worker::from(vec![Export(1), Export(9), Export(3), Export(2)])
+            .with_threads(1)
+            .unwrap();
+        worker.clock_in();
+        std::thread::sleep(Duration::from_millis(10));
+        worker.pause();
+        assert!(worker.is_paused());
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(*EXPORTED.lock().unwrap(), vec![9]);
+        assert_eq!(worker.running(), 0);
+        assert_eq!(worker.queue().len(), 3);
+        assert!(worker
+            .queue()
+            .peek()
+            .is_some_and(|entry| entry.attempts == 0));
+
+        worker.assign_one(Export(5)).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(worker.queue().len(), 4);
+
+        worker.resume();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!worker.is_paused());
+        assert_eq!(*EXPORTED.lock().unwrap(), vec![9, 5, 3, 2, 1]);
+    }
 }
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use toretsu::error::Error;
+    use toretsu::output::Produce;
+    use toretsu::task::{Failure, Task};
+    use toretsu::worker::Worker;
+    use toretsu::workflow::Workflow;
+
+    static UNDONE: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+    #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+    enum Etl {
+        Extract(i64),
+        Double(Vec<i64>),
+        Sum(Vec<i64>),
+        Broken,
+        Undo(&'static str),
+    }
+
+    impl Task for Etl {
+        fn process(&mut self) {}
+
+        fn try_process(&mut self) -> Result<(), Failure> {
+            match self {
+                Self::Broken => Err("source is unreachable".into()),
+                Self::Undo(name) => {
+                    UNDONE.lock().unwrap().push(name);
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    impl Produce for Etl {
+        type Output = i64;
+
+        fn output(&mut self) -> Option<i64> {
+            match self {
+                Self::Extract(n) => Some(*n),
+                Self::Double(upstream) => Some(upstream.iter().sum::<i64>() * 2),
+                Self::Sum(upstream) => Some(upstream.iter().sum()),
+                Self::Broken => None,
+                Self::Undo(_) => None,
+            }
+        }
+
+        fn receive(&mut self, upstream: Vec<i64>) {
+            if let Self::Double(inputs) | Self::Sum(inputs) = self {
+                *inputs = upstream;
+            }
+        }
+    }
+
+    fn worker() -> Worker<Etl> {
+        let mut worker = Worker::new().with_threads(1).unwrap();
+        worker.clock_in();
+        worker
+    }
+
+    #[test]
+    fn workflow_chain() {
+        let worker = worker();
+        let mut flow = Workflow::new();
+        let steps = flow.chain(
+            &[],
+            [Etl::Extract(3), Etl::Double(vec![]), Etl::Double(vec![])],
+        );
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[2].index(), 2);
+
+        assert_eq!(flow.run(&worker).unwrap().wait().unwrap(), vec![12]);
+    }
+
+    #[test]
+    fn workflow_chord_and_diamond() {
+        let worker = worker();
+        let mut flow = Workflow::new();
+        flow.add(Etl::Extract(10));
+        let extracts = [Etl::Extract(1), Etl::Extract(2), Etl::Extract(3)];
+        flow.chord(&[], extracts, Etl::Sum(vec![]));
+
+        let top = flow.add(Etl::Extract(2));
+        let sides = flow.group(&[top], [Etl::Double(vec![]), Etl::Double(vec![])]);
+        flow.add_after(&sides, Etl::Sum(vec![]));
+        assert_eq!(flow.len(), 9);
+
+        let outputs = flow.run(&worker).unwrap().wait().unwrap();
+        assert_eq!(outputs, vec![10, 6, 8]);
+    }
+
+    #[test]
+    fn workflow_failure_compensates() {
+        let worker = worker();
+        let mut flow = Workflow::new();
+        let load = flow.add(Etl::Extract(1));
+        flow.compensate(load, Etl::Undo("load"));
+        let transform = flow.add_after(&[load], Etl::Double(vec![]));
+        flow.compensate(transform, Etl::Undo("transform"));
+        let broken = flow.add_after(&[transform], Etl::Broken);
+        flow.compensate(broken, Etl::Undo("broken"));
+        flow.add_after(&[broken], Etl::Sum(vec![]));
+
+        let err = flow.run(&worker).unwrap().wait().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "task failed: step 2 failed: source is unreachable"
+        );
+
+        let until = Instant::now() + Duration::from_secs(5);
+        while UNDONE.lock().unwrap().len() < 2 && Instant::now() < until {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(*UNDONE.lock().unwrap(), vec!["transform", "load"]);
+        assert_eq!(worker.dead_letters().len(), 1);
+    }
+
+    #[test]
+    fn workflow_invalid() {
+        let worker = worker();
+        let mut other = Workflow::new();
+        let stray = other.add(Etl::Extract(1));
+
+        let mut flow = Workflow::new();
+        flow.add_after(&[stray], Etl::Double(vec![]));
+        assert!(matches!(flow.run(&worker), Err(Error::Workflow(_))));
+
+        let empty: Workflow<Etl> = Workflow::new();
+        assert!(empty.is_empty());
+        assert!(empty.run(&worker).unwrap().wait().unwrap().is_empty());
+    }
+}